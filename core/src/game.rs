@@ -0,0 +1,765 @@
+//! One player's side of a game as a state machine, free of any IO.
+//!
+//! A driver feeds `Event`s in as the player acts and messages arrive, and
+//! carries out the `Action`s that come back: proving, sending, prompting for
+//! shots. Receipts are verified by the driver before their journals are
+//! handed over; `Match` checks that each journal continues the game.
+
+use crate::{
+    audit_game, audit_salvo_game, AuditError, BoardCommit, BoardInput, GameMode, GameRules,
+    GameState, HitType, PlacementError, Position, RoundCommit, RoundInput, SalvoCommit,
+    SalvoInput, ShotError,
+};
+use risc0_zkvm::sha::Digest;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Phase {
+    /// Waiting for our fleet
+    Placement,
+    /// Our board is committed, the opponent's is not yet verified
+    Handshake,
+    /// Our shots to fire, or fired and waiting for their proven result
+    MyTurn,
+    /// Waiting for the opponent's shots
+    TheirTurn,
+    /// A fleet is sunk; the loser still owes a proof of it
+    Finished { won: bool },
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Placement => write!(f, "placing ships"),
+            Self::Handshake => write!(f, "exchanging boards"),
+            Self::MyTurn => write!(f, "on our turn"),
+            Self::TheirTurn => write!(f, "on the opponent's turn"),
+            Self::Finished { .. } => write!(f, "after the game"),
+        }
+    }
+}
+
+/// The journal of the opponent's proof for our shots
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Proven {
+    Round(RoundCommit),
+    Salvo(SalvoCommit),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// Our fleet is placed
+    Placed(GameState),
+    /// The opponent's board commitment and the journal of its init proof
+    OpponentBoard { commitment: Digest, proven: BoardCommit },
+    /// We picked our shots for this turn
+    Fire(Vec<Position>),
+    /// The opponent fired at us
+    Incoming(Vec<Position>),
+    /// The opponent's claimed results for our shots and the journal proving them
+    Result {
+        shots: Vec<Position>,
+        hits: Vec<HitType>,
+        proven: Proven,
+    },
+    /// The journal of the opponent's fleet-sunk proof
+    Conceded(BoardCommit),
+}
+
+impl Event {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Placed(_) => "a placed fleet",
+            Self::OpponentBoard { .. } => "the opponent's board",
+            Self::Fire(_) => "firing",
+            Self::Incoming(_) => "an incoming shot",
+            Self::Result { .. } => "a shot result",
+            Self::Conceded(_) => "a concession",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Prove our board with the init guest and send the commitment
+    ProveBoard(BoardInput),
+    /// Wait for the opponent's board, answered with `Event::OpponentBoard`
+    AwaitBoard,
+    /// Pick this many shots, answered with `Event::Fire`
+    ChooseShots(usize),
+    /// Send our shots to the opponent
+    SendShots(Vec<Position>),
+    /// Wait for the proven result of our shots, answered with `Event::Result`
+    AwaitResult,
+    /// Prove the result of the opponent's shot with the round guest and send it
+    ProveRound { input: RoundInput, commit: RoundCommit },
+    /// Prove the results of the opponent's salvo with the salvo guest and send them
+    ProveSalvo { input: SalvoInput, commit: SalvoCommit },
+    /// Wait for the opponent's shots, answered with `Event::Incoming`
+    AwaitShots,
+    /// Their last ship went down; wait for them to prove it with `Event::Conceded`
+    AwaitConcession,
+    /// Our last ship went down; prove it with the fleet-sunk guest
+    Concede(BoardInput),
+}
+
+/// Why an event was refused
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MatchError {
+    /// The event does not fit the phase the match is in
+    OutOfTurn { phase: Phase, event: &'static str },
+    /// Our fleet is not valid for the rules
+    InvalidFleet(PlacementError),
+    /// Our shots break the rules
+    InvalidShots(String),
+    /// The opponent fired shots the rules do not allow
+    IllegalShots(String),
+    /// A proven journal from the opponent does not continue this game
+    BadProof(String),
+}
+
+impl Display for MatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfTurn { phase, event } => write!(f, "{} while {}", event, phase),
+            Self::InvalidFleet(error) => write!(f, "invalid fleet: {}", error),
+            Self::InvalidShots(message)
+            | Self::IllegalShots(message)
+            | Self::BadProof(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for MatchError {}
+
+/// Everything one player knows about a game, as proven so far
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Match {
+    session: Digest,
+    rules: GameRules,
+    starts_first: bool,
+    phase: Phase,
+
+    /// Empty until `Event::Placed`
+    my_state: GameState,
+    my_initial_state: GameState,
+    my_commitment: Digest,
+    my_rounds: u32,
+
+    opponent_commitment: Digest,
+    opponent_initial_commitment: Digest,
+    opponent_rounds: Vec<RoundCommit>,
+    opponent_salvos: Vec<SalvoCommit>,
+    opponent_sunk: usize,
+    /// Shots we fired and have no verified result for yet
+    pending_shots: Vec<Position>,
+}
+
+impl Match {
+    pub fn new(session: Digest, rules: GameRules, starts_first: bool) -> Self {
+        let empty = GameState::new(rules.clone(), [0; 16]);
+        Self {
+            session,
+            rules,
+            starts_first,
+            phase: Phase::Placement,
+            my_commitment: empty.commit(),
+            my_initial_state: empty.clone(),
+            my_state: empty,
+            my_rounds: 0,
+            opponent_commitment: Digest::default(),
+            opponent_initial_commitment: Digest::default(),
+            opponent_rounds: Vec::new(),
+            opponent_salvos: Vec::new(),
+            opponent_sunk: 0,
+            pending_shots: Vec::new(),
+        }
+    }
+
+    /// Apply one event, returning what it asks of the driver followed by
+    /// `next_actions` for the phase it leads to
+    pub fn handle(&mut self, event: Event) -> Result<Vec<Action>, MatchError> {
+        let mut actions = match (&self.phase, event) {
+            (Phase::Placement, Event::Placed(state)) => {
+                self.place(state)?;
+                Vec::new()
+            }
+            (Phase::Handshake, Event::OpponentBoard { commitment, proven }) => {
+                self.accept_board(commitment, proven)?;
+                Vec::new()
+            }
+            (Phase::MyTurn, Event::Fire(shots)) if self.awaiting_shots() => {
+                self.fire(&shots)?;
+                vec![Action::SendShots(shots)]
+            }
+            (Phase::MyTurn, Event::Result { shots, hits, proven })
+                if !self.pending_shots.is_empty() =>
+            {
+                self.accept_result(shots, hits, proven)?;
+                Vec::new()
+            }
+            (Phase::TheirTurn, Event::Incoming(shots)) => vec![self.take_fire(shots)?],
+            (Phase::MyTurn | Phase::TheirTurn, Event::Conceded(proven)) => {
+                self.accept_concession(proven)?;
+                Vec::new()
+            }
+            (phase, event) => {
+                return Err(MatchError::OutOfTurn {
+                    phase: phase.clone(),
+                    event: event.name(),
+                })
+            }
+        };
+
+        actions.extend(self.next_actions());
+        Ok(actions)
+    }
+
+    /// What the current phase asks of the driver; also where a resumed game picks up
+    pub fn next_actions(&self) -> Vec<Action> {
+        match self.phase {
+            Phase::Placement => Vec::new(),
+            Phase::Handshake => vec![
+                Action::ProveBoard(self.board_input()),
+                Action::AwaitBoard,
+            ],
+            Phase::MyTurn if !self.pending_shots.is_empty() => vec![Action::AwaitResult],
+            Phase::MyTurn if self.opponent_afloat() == 0 => vec![Action::AwaitConcession],
+            Phase::MyTurn => vec![Action::ChooseShots(self.salvo_size())],
+            Phase::TheirTurn => vec![Action::AwaitShots],
+            Phase::Finished { won: false } => vec![Action::Concede(self.board_input())],
+            Phase::Finished { won: true } => Vec::new(),
+        }
+    }
+
+    /// Forget shots the opponent never received, so they are chosen again
+    pub fn retract_shots(&mut self) {
+        self.pending_shots.clear();
+    }
+
+    /// Replay the opponent's proven turns against their revealed initial board
+    pub fn audit(&self, revealed: &GameState) -> Result<GameState, AuditError> {
+        match self.rules.mode {
+            GameMode::Classic => audit_game(
+                revealed,
+                &self.opponent_initial_commitment,
+                &self.session,
+                &self.opponent_rounds,
+            ),
+            GameMode::Salvo => audit_salvo_game(
+                revealed,
+                &self.opponent_initial_commitment,
+                &self.session,
+                &self.opponent_salvos,
+            ),
+        }
+    }
+
+    pub fn phase(&self) -> &Phase {
+        &self.phase
+    }
+
+    pub fn session(&self) -> Digest {
+        self.session
+    }
+
+    pub fn rules(&self) -> &GameRules {
+        &self.rules
+    }
+
+    pub fn starts_first(&self) -> bool {
+        self.starts_first
+    }
+
+    pub fn my_state(&self) -> &GameState {
+        &self.my_state
+    }
+
+    pub fn my_initial_state(&self) -> &GameState {
+        &self.my_initial_state
+    }
+
+    pub fn my_commitment(&self) -> Digest {
+        self.my_commitment
+    }
+
+    pub fn opponent_initial_commitment(&self) -> Digest {
+        self.opponent_initial_commitment
+    }
+
+    pub fn pending_shots(&self) -> &[Position] {
+        &self.pending_shots
+    }
+
+    /// Turns we have proven to the opponent
+    pub fn turns_proven(&self) -> u32 {
+        self.my_rounds
+    }
+
+    /// Turns of the opponent's we have verified
+    pub fn turns_verified(&self) -> u32 {
+        match self.rules.mode {
+            GameMode::Classic => self.opponent_rounds.len() as u32,
+            GameMode::Salvo => self.opponent_salvos.len() as u32,
+        }
+    }
+
+    /// Opponent ships not yet proven sunk
+    pub fn opponent_afloat(&self) -> usize {
+        self.rules.total_ships() - self.opponent_sunk
+    }
+
+    /// How many shots we fire this turn
+    pub fn salvo_size(&self) -> usize {
+        match self.rules.mode {
+            GameMode::Classic => 1,
+            GameMode::Salvo => self.my_state.ships_afloat() as usize,
+        }
+    }
+
+    fn awaiting_shots(&self) -> bool {
+        self.pending_shots.is_empty() && self.opponent_afloat() > 0
+    }
+
+    fn board_input(&self) -> BoardInput {
+        BoardInput {
+            session: self.session,
+            state: self.my_state.clone(),
+        }
+    }
+
+    fn place(&mut self, state: GameState) -> Result<(), MatchError> {
+        if state.rules != self.rules {
+            return Err(MatchError::InvalidFleet(PlacementError::InvalidRules));
+        }
+        state.check().map_err(MatchError::InvalidFleet)?;
+
+        self.my_commitment = state.commit();
+        self.my_initial_state = state.clone();
+        self.my_state = state;
+        self.phase = Phase::Handshake;
+        Ok(())
+    }
+
+    fn accept_board(&mut self, commitment: Digest, proven: BoardCommit) -> Result<(), MatchError> {
+        if proven.session != self.session {
+            return Err(bad_proof("receipt was proven for a different game session"));
+        }
+        if proven.rules != self.rules {
+            return Err(bad_proof("board was checked against different rules than agreed"));
+        }
+        if proven.state != commitment {
+            return Err(MatchError::BadProof(format!(
+                "receipt proves board {:?} but the commitment sent was {:?}",
+                proven.state, commitment
+            )));
+        }
+
+        self.opponent_commitment = commitment;
+        self.opponent_initial_commitment = commitment;
+        self.phase = if self.starts_first {
+            Phase::MyTurn
+        } else {
+            Phase::TheirTurn
+        };
+        Ok(())
+    }
+
+    fn fire(&mut self, shots: &[Position]) -> Result<(), MatchError> {
+        let size = self.salvo_size();
+        if shots.len() != size {
+            return Err(MatchError::InvalidShots(format!(
+                "{} shots fired but {} are allowed",
+                shots.len(),
+                size
+            )));
+        }
+        for (i, shot) in shots.iter().enumerate() {
+            if !shot.in_bounds(&self.rules) {
+                return Err(MatchError::InvalidShots(format!("{} is outside the board", shot)));
+            }
+            if shots[..i].contains(shot) {
+                return Err(MatchError::InvalidShots(format!("{} is already in this salvo", shot)));
+            }
+        }
+
+        self.pending_shots = shots.to_vec();
+        Ok(())
+    }
+
+    fn accept_result(
+        &mut self,
+        shots: Vec<Position>,
+        hits: Vec<HitType>,
+        proven: Proven,
+    ) -> Result<(), MatchError> {
+        if shots != self.pending_shots {
+            return Err(MatchError::BadProof(match shots[..] {
+                [shot] => format!("result is for {}, which we did not fire at", shot),
+                _ => "result is for a salvo we did not fire".to_string(),
+            }));
+        }
+
+        let turn = self.turns_verified();
+        let (session, proven_turn, old_state) = match &proven {
+            Proven::Round(commit) => (commit.session, commit.turn, commit.old_state),
+            Proven::Salvo(commit) => (commit.session, commit.turn, commit.old_state),
+        };
+        if session != self.session {
+            return Err(bad_proof("proof is from a different game session"));
+        }
+        if proven_turn != turn {
+            return Err(MatchError::BadProof(format!(
+                "proof is for turn {} but turn {} was expected",
+                proven_turn, turn
+            )));
+        }
+        if old_state != self.opponent_commitment {
+            return Err(bad_proof("proof uses the wrong state commitment"));
+        }
+
+        let sunk = hits.iter().filter(|hit| matches!(hit, HitType::Sunk(_))).count();
+        let turn_passes = match (self.rules.mode, proven) {
+            (GameMode::Classic, Proven::Round(commit)) => {
+                if [commit.shot] != shots[..] {
+                    return Err(bad_proof("proof is for the wrong shot position"));
+                }
+                if hits != [commit.hit.clone()] {
+                    return Err(bad_proof("proof hit type doesn't match"));
+                }
+                let passes = matches!(commit.hit, HitType::Miss | HitType::Repeat);
+                self.opponent_commitment = commit.new_state;
+                self.opponent_rounds.push(commit);
+                passes
+            }
+            (GameMode::Salvo, Proven::Salvo(commit)) => {
+                if commit.shots != shots {
+                    return Err(bad_proof("proof is for the wrong salvo"));
+                }
+                if commit.hits != hits {
+                    return Err(bad_proof("proof hit types don't match"));
+                }
+                if commit.ships_afloat as usize != self.opponent_afloat() - sunk {
+                    return Err(bad_proof("proof disagrees with the ships sunk so far"));
+                }
+                self.opponent_commitment = commit.new_state;
+                self.opponent_salvos.push(commit);
+                true
+            }
+            (mode, _) => {
+                return Err(MatchError::BadProof(format!("proof is not for a {} game", mode)))
+            }
+        };
+
+        self.opponent_sunk += sunk;
+        self.pending_shots.clear();
+        // With their last ship down we wait on our turn for them to concede
+        if turn_passes && self.opponent_afloat() > 0 {
+            self.phase = Phase::TheirTurn;
+        }
+        Ok(())
+    }
+
+    /// Apply the opponent's shots to our board and hand back the proof to make
+    fn take_fire(&mut self, shots: Vec<Position>) -> Result<Action, MatchError> {
+        let allowed = match self.rules.mode {
+            GameMode::Classic => 1,
+            // The opponent's proven sunk ships fix how many shots they may fire
+            GameMode::Salvo => self.opponent_afloat(),
+        };
+        if shots.len() != allowed {
+            return Err(MatchError::IllegalShots(format!(
+                "{} shots fired but {} are allowed",
+                shots.len(),
+                allowed
+            )));
+        }
+        if let Some(shot) = shots.iter().find(|shot| !shot.in_bounds(&self.rules)) {
+            return Err(MatchError::IllegalShots(format!("Shot at {} is outside the board", shot)));
+        }
+
+        let turn = self.my_rounds;
+        let state = self.my_state.clone();
+        let old_state = self.my_commitment;
+        let illegal = |error: ShotError| MatchError::IllegalShots(error.to_string());
+
+        let (action, turn_passes) = match self.rules.mode {
+            GameMode::Classic => {
+                let shot = shots[0];
+                let hit = self.my_state.apply_shot(shot).map_err(illegal)?;
+                let turn_passes = matches!(hit, HitType::Miss | HitType::Repeat);
+                let commit = RoundCommit {
+                    session: self.session,
+                    turn,
+                    old_state,
+                    new_state: self.my_state.commit(),
+                    shot,
+                    hit,
+                };
+                let input = RoundInput { session: self.session, turn, state, shot };
+                (Action::ProveRound { input, commit }, turn_passes)
+            }
+            GameMode::Salvo => {
+                let hits = self.my_state.apply_salvo(&shots).map_err(illegal)?;
+                let commit = SalvoCommit {
+                    session: self.session,
+                    turn,
+                    old_state,
+                    new_state: self.my_state.commit(),
+                    shots: shots.clone(),
+                    hits,
+                    ships_afloat: self.my_state.ships_afloat(),
+                };
+                let input = SalvoInput { session: self.session, turn, state, shots };
+                (Action::ProveSalvo { input, commit }, true)
+            }
+        };
+
+        self.my_commitment = self.my_state.commit();
+        self.my_rounds += 1;
+        if self.my_state.all_sunk() {
+            self.phase = Phase::Finished { won: false };
+        } else if turn_passes {
+            self.phase = Phase::MyTurn;
+        }
+        Ok(action)
+    }
+
+    fn accept_concession(&mut self, proven: BoardCommit) -> Result<(), MatchError> {
+        if proven.session != self.session {
+            return Err(bad_proof("game over proof is from a different game session"));
+        }
+        if proven.state != self.opponent_commitment {
+            return Err(bad_proof("game over proof is not for the opponent's current board"));
+        }
+
+        self.pending_shots.clear();
+        self.phase = Phase::Finished { won: true };
+        Ok(())
+    }
+}
+
+fn bad_proof(message: &str) -> MatchError {
+    MatchError::BadProof(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Direction, FleetEntry, Ship, ShipClass};
+
+    const SESSION: Digest = Digest::new([7; 8]);
+
+    fn small_rules(mode: GameMode) -> GameRules {
+        GameRules {
+            width: 4,
+            height: 4,
+            fleet: vec![FleetEntry {
+                class: ShipClass::new("Destroyer", 'D', 2),
+                count: 2,
+            }],
+            mode,
+        }
+    }
+
+    fn fleet(rules: &GameRules, rows: [u32; 2]) -> GameState {
+        let mut state = GameState::new(rules.clone(), [rows[0] as u8; 16]);
+        for row in rows {
+            let class = rules.fleet[0].class.clone();
+            state.add_ship(Ship::new(class, (0, row), Direction::Horizontal)).unwrap();
+        }
+        state
+    }
+
+    fn board_commit(state: &GameState) -> BoardCommit {
+        BoardCommit {
+            session: SESSION,
+            rules: state.rules.clone(),
+            state: state.commit(),
+        }
+    }
+
+    /// Two matches past the handshake, the first one to move first
+    fn start(mode: GameMode) -> [Match; 2] {
+        let rules = small_rules(mode);
+        let fleets = [fleet(&rules, [0, 2]), fleet(&rules, [1, 3])];
+        let mut players = [
+            Match::new(SESSION, rules.clone(), true),
+            Match::new(SESSION, rules, false),
+        ];
+
+        for (player, state) in players.iter_mut().zip(&fleets) {
+            let actions = player.handle(Event::Placed(state.clone())).unwrap();
+            assert!(matches!(actions[..], [Action::ProveBoard(_), Action::AwaitBoard]));
+        }
+        for (player, theirs) in players.iter_mut().zip(fleets.iter().rev()) {
+            let proven = board_commit(theirs);
+            player
+                .handle(Event::OpponentBoard { commitment: proven.state, proven })
+                .unwrap();
+        }
+        assert_eq!(players[0].next_actions(), [Action::ChooseShots(players[0].salvo_size())]);
+        assert_eq!(players[1].next_actions(), [Action::AwaitShots]);
+        players
+    }
+
+    /// Pass the opponent's answer to `shots` back to the shooter as a result event
+    fn fire(shooter: &mut Match, target: &mut Match, shots: Vec<Position>) -> Vec<Action> {
+        let actions = shooter.handle(Event::Fire(shots.clone())).unwrap();
+        assert_eq!(actions, [Action::SendShots(shots.clone()), Action::AwaitResult]);
+
+        let answer = target.handle(Event::Incoming(shots.clone())).unwrap();
+        let event = match &answer[0] {
+            Action::ProveRound { commit, .. } => Event::Result {
+                shots,
+                hits: vec![commit.hit.clone()],
+                proven: Proven::Round(commit.clone()),
+            },
+            Action::ProveSalvo { commit, .. } => Event::Result {
+                shots,
+                hits: commit.hits.clone(),
+                proven: Proven::Salvo(commit.clone()),
+            },
+            action => panic!("expected a proof, got {:?}", action),
+        };
+        let actions = shooter.handle(event).unwrap();
+
+        if let [.., Action::Concede(input)] = &answer[..] {
+            assert_eq!(actions, [Action::AwaitConcession]);
+            return shooter.handle(Event::Conceded(board_commit(&input.state))).unwrap();
+        }
+        actions
+    }
+
+    /// Both sides fire at every cell in order until a fleet is sunk
+    fn play(mode: GameMode) -> [Match; 2] {
+        let mut players = start(mode);
+        let cells: Vec<Position> = players[0].rules().positions().collect();
+        let mut targets = [cells.iter().copied(), cells.iter().copied()];
+
+        while !matches!(players[0].phase(), Phase::Finished { .. }) {
+            let me = if *players[0].phase() == Phase::MyTurn { 0 } else { 1 };
+            let [first, second] = &mut players;
+            let (shooter, target) = if me == 0 { (first, second) } else { (second, first) };
+
+            let size = shooter.salvo_size();
+            let shots: Vec<Position> = targets[me].by_ref().take(size).collect();
+            fire(shooter, target, shots);
+        }
+        players
+    }
+
+    #[test]
+    fn test_match_classic() {
+        let players = play(GameMode::Classic);
+
+        // Both fleets start in the first column, so the second player gets there first
+        assert_eq!(*players[0].phase(), Phase::Finished { won: false });
+        assert_eq!(*players[1].phase(), Phase::Finished { won: true });
+        assert!(matches!(players[0].next_actions()[..], [Action::Concede(_)]));
+        assert_eq!(players[1].next_actions(), []);
+
+        assert!(players[0].audit(players[1].my_initial_state()).is_ok());
+        assert!(players[1].audit(players[0].my_initial_state()).is_ok());
+        assert_eq!(players[0].turns_verified(), players[1].turns_proven());
+    }
+
+    #[test]
+    fn test_match_salvo() {
+        let players = play(GameMode::Salvo);
+        assert!(matches!(players[0].phase(), Phase::Finished { .. }));
+        assert!(players[0].audit(players[1].my_initial_state()).is_ok());
+        assert!(players[1].audit(players[0].my_initial_state()).is_ok());
+    }
+
+    #[test]
+    fn test_match_turns() {
+        let [mut first, mut second] = start(GameMode::Classic);
+
+        // A miss passes the turn, a hit keeps it
+        fire(&mut first, &mut second, vec![Position::new(3, 0)]);
+        assert_eq!(*first.phase(), Phase::TheirTurn);
+        assert_eq!(*second.phase(), Phase::MyTurn);
+        fire(&mut second, &mut first, vec![Position::new(0, 0)]);
+        assert_eq!(*second.phase(), Phase::MyTurn);
+
+        // Events that do not fit the phase are refused without changing anything
+        assert_eq!(
+            first.handle(Event::Fire(vec![Position::new(0, 1)])),
+            Err(MatchError::OutOfTurn {
+                phase: Phase::TheirTurn,
+                event: "firing"
+            })
+        );
+        assert!(matches!(
+            second.handle(Event::Fire(vec![Position::new(0, 1), Position::new(1, 1)])),
+            Err(MatchError::InvalidShots(_))
+        ));
+        assert!(matches!(
+            first.handle(Event::Incoming(vec![Position::new(9, 9)])),
+            Err(MatchError::IllegalShots(_))
+        ));
+        assert_eq!(first.turns_proven(), 1);
+        assert_eq!(second.next_actions(), [Action::ChooseShots(1)]);
+    }
+
+    #[test]
+    fn test_match_rejects_proofs() {
+        let [mut first, mut second] = start(GameMode::Classic);
+        let shot = Position::new(0, 1);
+        first.handle(Event::Fire(vec![shot])).unwrap();
+
+        let answer = second.handle(Event::Incoming(vec![shot])).unwrap();
+        let Action::ProveRound { commit, .. } = &answer[0] else {
+            panic!("expected a round proof");
+        };
+        let result = |commit: RoundCommit, hit: HitType| Event::Result {
+            shots: vec![shot],
+            hits: vec![hit],
+            proven: Proven::Round(commit),
+        };
+        let rejected = |event| {
+            let mut first = first.clone();
+            match first.handle(event) {
+                Err(MatchError::BadProof(message)) => message,
+                other => panic!("expected a bad proof, got {:?}", other),
+            }
+        };
+
+        assert_eq!(
+            rejected(result(commit.clone(), HitType::Miss)),
+            "proof hit type doesn't match"
+        );
+        let replayed = RoundCommit { turn: 1, ..commit.clone() };
+        assert_eq!(
+            rejected(result(replayed, commit.hit.clone())),
+            "proof is for turn 1 but turn 0 was expected"
+        );
+        let forked = RoundCommit { old_state: Digest::default(), ..commit.clone() };
+        assert_eq!(
+            rejected(result(forked, commit.hit.clone())),
+            "proof uses the wrong state commitment"
+        );
+        let elsewhere = Event::Result {
+            shots: vec![Position::new(1, 1)],
+            hits: vec![commit.hit.clone()],
+            proven: Proven::Round(commit.clone()),
+        };
+        assert_eq!(rejected(elsewhere), "result is for (1, 1), which we did not fire at");
+
+        // A concession must be for the board as it stands
+        let early = BoardCommit {
+            session: SESSION,
+            rules: first.rules().clone(),
+            state: Digest::default(),
+        };
+        assert_eq!(
+            rejected(Event::Conceded(early)),
+            "game over proof is not for the opponent's current board"
+        );
+
+        first.handle(result(commit.clone(), commit.hit.clone())).unwrap();
+        assert_eq!(first.turns_verified(), 1);
+    }
+}
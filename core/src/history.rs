@@ -0,0 +1,231 @@
+//! One player's whole game folded into a single rolling proof.
+//!
+//! The history guest starts from a board's init receipt and takes one more of
+//! that board's proven results per step, verifying the previous history
+//! receipt and the new result receipt as assumptions (`env::verify`). The last
+//! journal attests to the initial board, every result in order and the final
+//! board, so one receipt stands for the whole game.
+
+use crate::{BoardCommit, GameRules, HitType, RoundCommit, SalvoCommit};
+use risc0_zkvm::sha::{Digest, Sha256};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Image IDs the history guest verifies its assumptions against. A guest
+/// cannot know its own image ID, so `history` is an input like the others;
+/// it is committed, and whoever verifies the final receipt checks all four.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HistoryIds {
+    pub history: Digest,
+    pub init: Digest,
+    pub round: Digest,
+    pub salvo: Digest,
+}
+
+/// One step of the history guest
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum HistoryStep {
+    /// Begin from the init guest's journal
+    Start(BoardCommit),
+    /// Extend the previous history by one proven shot result
+    Round {
+        previous: HistoryCommit,
+        round: RoundCommit,
+    },
+    /// Extend the previous history by one proven salvo
+    Salvo {
+        previous: HistoryCommit,
+        salvo: SalvoCommit,
+    },
+}
+
+/// Input to the history guest
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HistoryInput {
+    pub ids: HistoryIds,
+    pub step: HistoryStep,
+}
+
+/// Journal of the history guest: a board's game so far
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HistoryCommit {
+    pub ids: HistoryIds,
+    pub session: Digest,
+    pub rules: GameRules,
+    /// The board commitment proven valid by the init receipt
+    pub initial_state: Digest,
+    /// The board commitment after the last proven result
+    pub state: Digest,
+    /// Rounds or salvos proven against the board
+    pub turns: u32,
+    pub ships_afloat: u32,
+    /// Hash chain over the init journal and every result journal, in order
+    pub results: Digest,
+}
+
+/// A step that does not continue the history it claims to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryError(pub String);
+
+impl Display for HistoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+impl HistoryInput {
+    /// The receipts this step rests on, as image ID and journal words: the
+    /// guest verifies each one before trusting the journals it was given
+    pub fn assumptions(&self) -> Vec<(Digest, Vec<u32>)> {
+        match &self.step {
+            HistoryStep::Start(board) => vec![(self.ids.init, journal(board))],
+            HistoryStep::Round { previous, round } => vec![
+                (self.ids.history, journal(previous)),
+                (self.ids.round, journal(round)),
+            ],
+            HistoryStep::Salvo { previous, salvo } => vec![
+                (self.ids.history, journal(previous)),
+                (self.ids.salvo, journal(salvo)),
+            ],
+        }
+    }
+
+    /// The history after this step
+    pub fn apply(&self) -> Result<HistoryCommit, HistoryError> {
+        let (previous, session, old_state, new_state, ships_afloat, step) = match &self.step {
+            HistoryStep::Start(board) => {
+                return Ok(HistoryCommit {
+                    ids: self.ids,
+                    session: board.session,
+                    rules: board.rules.clone(),
+                    initial_state: board.state,
+                    state: board.state,
+                    turns: 0,
+                    ships_afloat: board.rules.total_ships() as u32,
+                    results: hash(&journal(board)),
+                });
+            }
+            HistoryStep::Round { previous, round } => {
+                let sunk = matches!(round.hit, HitType::Sunk(_)) as u32;
+                let afloat = previous.ships_afloat.saturating_sub(sunk);
+                (previous, round.session, round.old_state, round.new_state, afloat, round.turn)
+            }
+            HistoryStep::Salvo { previous, salvo } => (
+                previous,
+                salvo.session,
+                salvo.old_state,
+                salvo.new_state,
+                salvo.ships_afloat,
+                salvo.turn,
+            ),
+        };
+
+        if previous.ids != self.ids {
+            return Err(HistoryError("the previous history used other guests".to_string()));
+        }
+        if session != previous.session {
+            return Err(HistoryError("the result is from another session".to_string()));
+        }
+        if step != previous.turns {
+            return Err(HistoryError(format!(
+                "expected turn {}, the result is for turn {}",
+                previous.turns, step
+            )));
+        }
+        if old_state != previous.state {
+            return Err(HistoryError("the result is for a different board".to_string()));
+        }
+
+        // The result journal is the second assumption
+        let (_, result) = &self.assumptions()[1];
+        let mut chained = previous.results.as_bytes().to_vec();
+        chained.extend_from_slice(hash(result).as_bytes());
+        Ok(HistoryCommit {
+            state: new_state,
+            turns: previous.turns + 1,
+            ships_afloat,
+            results: *risc0_zkvm::sha::Impl::hash_bytes(&chained),
+            ..previous.clone()
+        })
+    }
+}
+
+/// Journal words as the guest that committed `commit` wrote them
+fn journal<T: Serialize>(commit: &T) -> Vec<u32> {
+    risc0_zkvm::serde::to_vec(commit).expect("journals always serialize")
+}
+
+fn hash(words: &[u32]) -> Digest {
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+    *risc0_zkvm::sha::Impl::hash_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Direction, GameState, Position, Ship};
+
+    #[test]
+    fn test_history() {
+        let rules = GameRules::classic();
+        let mut state = GameState::new(rules.clone(), [7; 16]);
+        for (y, entry) in rules.fleet.iter().enumerate() {
+            let ship = Ship::new(entry.class.clone(), (0, y as u32), Direction::Horizontal);
+            state.add_ship(ship).unwrap();
+        }
+        let ids = HistoryIds {
+            history: Digest::from([1u32; 8]),
+            init: Digest::from([2u32; 8]),
+            round: Digest::from([3u32; 8]),
+            salvo: Digest::from([4u32; 8]),
+        };
+        let session = Digest::from([9u32; 8]);
+        let board = BoardCommit { session, rules: rules.clone(), state: state.commit() };
+        let start = HistoryInput { ids, step: HistoryStep::Start(board) };
+        assert_eq!(start.assumptions()[0].0, ids.init);
+        let mut history = start.apply().unwrap();
+
+        // Sink the whole fleet one proven shot at a time
+        let cells: Vec<Position> = state.ships.iter().flat_map(|ship| ship.points()).collect();
+        let mut rounds = Vec::new();
+        for (turn, shot) in cells.into_iter().enumerate() {
+            let old_state = state.commit();
+            let hit = state.apply_shot(shot).unwrap();
+            let round = RoundCommit {
+                session,
+                turn: turn as u32,
+                old_state,
+                new_state: state.commit(),
+                shot,
+                hit,
+            };
+            rounds.push(round.clone());
+
+            let step = HistoryInput { ids, step: HistoryStep::Round { previous: history, round } };
+            assert_eq!(step.assumptions()[0].0, ids.history);
+            history = step.apply().unwrap();
+        }
+        assert_eq!(history.state, state.commit());
+        assert_eq!(history.turns as usize, rounds.len());
+        assert_eq!(history.ships_afloat, 0);
+
+        // Results out of order, from another board or another session are refused
+        let replayed = HistoryStep::Round { previous: history.clone(), round: rounds[0].clone() };
+        assert!(HistoryInput { ids, step: replayed }.apply().is_err());
+        let mut other = rounds.last().unwrap().clone();
+        other.turn = history.turns;
+        assert!(HistoryInput {
+            ids,
+            step: HistoryStep::Round { previous: history.clone(), round: other.clone() }
+        }
+        .apply()
+        .is_err());
+        other.old_state = history.state;
+        other.session = Digest::default();
+        assert!(HistoryInput { ids, step: HistoryStep::Round { previous: history, round: other } }
+            .apply()
+            .is_err());
+    }
+}
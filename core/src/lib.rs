@@ -9,45 +9,43 @@ use rand::{
     Rng,
 };
 
-pub const NUM_SHIPS: usize = 5;
-pub const BOARD_SIZE: usize = 10;
+/// Longest ship a fleet may contain; hits are tracked in a `u8` mask
+pub const MAX_SHIP_SPAN: u32 = 8;
+/// Widest or tallest board the rules allow
+pub const MAX_BOARD_SIZE: u32 = 26;
+
+pub mod game;
+pub mod history;
 
 // ============================================================================
 // Basic Types
 // ============================================================================
 
-#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize, Hash)]
-pub enum ShipClass {
-    Carrier,
-    Battleship,
-    Cruiser,
-    Submarine,
-    Destroyer,
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, Hash)]
+pub struct ShipClass {
+    pub name: String,
+    /// Letter used for this class on board displays and board files
+    pub symbol: char,
+    pub span: u32,
 }
 
 impl ShipClass {
-    pub fn span(&self) -> u32 {
-        match self {
-            ShipClass::Carrier => 5,
-            ShipClass::Battleship => 4,
-            ShipClass::Cruiser => 3,
-            ShipClass::Submarine => 3,
-            ShipClass::Destroyer => 2,
+    pub fn new(name: impl Into<String>, symbol: char, span: u32) -> Self {
+        Self {
+            name: name.into(),
+            symbol,
+            span,
         }
     }
 
     pub fn sunk_mask(&self) -> u8 {
-        (1u8 << self.span()) - 1
+        ((1u16 << self.span) - 1) as u8
     }
+}
 
-    pub const fn list() -> &'static [ShipClass] {
-        &[
-            Self::Carrier,
-            Self::Battleship,
-            Self::Cruiser,
-            Self::Submarine,
-            Self::Destroyer,
-        ]
+impl Display for ShipClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
     }
 }
 
@@ -75,8 +73,8 @@ impl Position {
         }
     }
 
-    pub fn in_bounds(&self) -> bool {
-        self.x < BOARD_SIZE as u32 && self.y < BOARD_SIZE as u32
+    pub fn in_bounds(&self, rules: &GameRules) -> bool {
+        self.x < rules.width && self.y < rules.height
     }
 }
 
@@ -118,6 +116,145 @@ impl Distribution<Direction> for Standard {
     }
 }
 
+// ============================================================================
+// Game Rules
+// ============================================================================
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Hash)]
+pub struct FleetEntry {
+    pub class: ShipClass,
+    pub count: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Hash)]
+pub enum GameMode {
+    /// One shot at a time; a hit earns another shot
+    Classic,
+    /// One shot per surviving ship each turn; turns always alternate
+    Salvo,
+}
+
+impl Display for GameMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Classic => write!(f, "classic"),
+            Self::Salvo => write!(f, "salvo"),
+        }
+    }
+}
+
+/// Board dimensions, fleet composition and game mode both players agree on
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Hash)]
+pub struct GameRules {
+    pub width: u32,
+    pub height: u32,
+    pub fleet: Vec<FleetEntry>,
+    pub mode: GameMode,
+}
+
+impl GameRules {
+    /// The classic 10x10 board with one of each of the five standard ships
+    pub fn classic() -> Self {
+        let fleet = [
+            ("Carrier", 'A', 5),
+            ("Battleship", 'B', 4),
+            ("Cruiser", 'C', 3),
+            ("Submarine", 'S', 3),
+            ("Destroyer", 'D', 2),
+        ]
+        .into_iter()
+        .map(|(name, symbol, span)| FleetEntry {
+            class: ShipClass::new(name, symbol, span),
+            count: 1,
+        })
+        .collect();
+
+        Self {
+            width: 10,
+            height: 10,
+            fleet,
+            mode: GameMode::Classic,
+        }
+    }
+
+    pub fn check(&self) -> bool {
+        // Check the board is a sensible size
+        if !(1..=MAX_BOARD_SIZE).contains(&self.width) || !(1..=MAX_BOARD_SIZE).contains(&self.height)
+        {
+            return false;
+        }
+
+        // Check every class fits on the board and in the hit mask
+        if self.fleet.is_empty() {
+            return false;
+        }
+        for entry in &self.fleet {
+            let span = entry.class.span;
+            if entry.count == 0
+                || !(1..=MAX_SHIP_SPAN).contains(&span)
+                || (span > self.width && span > self.height)
+            {
+                return false;
+            }
+        }
+
+        // Check class names and symbols are unique
+        for (i, a) in self.fleet.iter().enumerate() {
+            for b in self.fleet.iter().skip(i + 1) {
+                if a.class.name == b.class.name || a.class.symbol == b.class.symbol {
+                    return false;
+                }
+            }
+        }
+
+        // Check the fleet can cover no more cells than the board has; the rules
+        // come from the peer, so a count that overflows is refused, not wrapped
+        let cells = self.fleet.iter().try_fold(0u32, |cells, e| {
+            e.class.span.checked_mul(e.count).and_then(|ship| cells.checked_add(ship))
+        });
+        cells.is_some_and(|cells| cells <= self.width * self.height)
+    }
+
+    pub fn class(&self, name: &str) -> Option<&ShipClass> {
+        self.fleet
+            .iter()
+            .map(|entry| &entry.class)
+            .find(|class| class.name == name)
+    }
+
+    pub fn count_of(&self, class: &ShipClass) -> u32 {
+        self.fleet
+            .iter()
+            .find(|entry| entry.class == *class)
+            .map_or(0, |entry| entry.count)
+    }
+
+    pub fn total_ships(&self) -> usize {
+        self.fleet.iter().map(|entry| entry.count as usize).sum()
+    }
+
+    /// Every ship to place, with classes repeated `count` times, largest first
+    pub fn ships_to_place(&self) -> Vec<&ShipClass> {
+        let mut classes: Vec<&ShipClass> = self
+            .fleet
+            .iter()
+            .flat_map(|entry| std::iter::repeat_n(&entry.class, entry.count as usize))
+            .collect();
+        classes.sort_by_key(|class| std::cmp::Reverse(class.span));
+        classes
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| Position::new(x, y)))
+    }
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
 // ============================================================================
 // Ship Structure
 // ============================================================================
@@ -145,15 +282,15 @@ impl Ship {
     }
 
     pub fn points(&self) -> impl Iterator<Item = Position> + '_ {
-        (0..self.class.span()).map(|offset| self.pos.step(self.dir, offset))
+        (0..self.class.span).map(|offset| self.pos.step(self.dir, offset))
     }
 
     pub fn intersects(&self, other: &Self) -> bool {
         self.points().any(|p| other.points().any(|q| p == q))
     }
 
-    pub fn in_bounds(&self) -> bool {
-        self.pos.in_bounds() && self.pos.step(self.dir, self.class.span() - 1).in_bounds()
+    pub fn in_bounds(&self, rules: &GameRules) -> bool {
+        self.pos.in_bounds(rules) && self.pos.step(self.dir, self.class.span - 1).in_bounds(rules)
     }
 
     pub fn apply_shot(&mut self, shot: Position) -> HitType {
@@ -162,7 +299,7 @@ impl Ship {
             Some(hit_index) => {
                 self.hit_mask |= 1 << hit_index;
                 if self.hit_mask == self.class.sunk_mask() {
-                    HitType::Sunk(self.class)
+                    HitType::Sunk(self.class.clone())
                 } else {
                     HitType::Hit
                 }
@@ -178,105 +315,187 @@ impl Ship {
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct GameState {
+    pub rules: GameRules,
     pub ships: Vec<Ship>,
+    /// Every cell the opponent has fired at, in order
+    pub shots: Vec<Position>,
     pub pepper: [u8; 16],
 }
 
 impl GameState {
-    pub fn new(pepper: [u8; 16]) -> Self {
+    pub fn new(rules: GameRules, pepper: [u8; 16]) -> Self {
         Self {
+            rules,
             ships: Vec::new(),
+            shots: Vec::new(),
             pepper,
         }
     }
 
-    pub fn check(&self) -> bool {
+    pub fn check(&self) -> Result<(), PlacementError> {
+        // Check the rules themselves are playable
+        if !self.rules.check() {
+            return Err(PlacementError::InvalidRules);
+        }
+
+        // Check no shots have been fired yet
+        if !self.shots.is_empty() || self.ships.iter().any(|ship| ship.hit_mask != 0) {
+            return Err(PlacementError::AlreadyShot);
+        }
+
+        // Check every ship is of a class the rules know, before any arithmetic
+        // on a span the rules never vetted
+        if let Some(ship) = self.ships.iter().find(|ship| self.rules.count_of(&ship.class) == 0) {
+            return Err(PlacementError::UnknownClass(ship.clone()));
+        }
+
         // Check all ships are in bounds
         for ship in &self.ships {
-            if !ship.in_bounds() {
-                return false;
+            if !ship.in_bounds(&self.rules) {
+                return Err(PlacementError::OutOfBounds(ship.clone()));
             }
         }
 
-        // Check each ship class appears exactly once
-        let mut classes = ShipClass::list().to_vec();
-        for ship in &self.ships {
-            if let Some(pos) = classes.iter().position(|&c| c == ship.class) {
-                classes.swap_remove(pos);
-            } else {
-                return false;
+        // Check the fleet matches the rules exactly
+        for (i, ship) in self.ships.iter().enumerate() {
+            let allowed = self.rules.count_of(&ship.class);
+            let before = self.ships[..i].iter().filter(|s| s.class == ship.class).count() as u32;
+            if before >= allowed {
+                return Err(PlacementError::DuplicateClass(ship.clone()));
             }
         }
-        if !classes.is_empty() {
-            return false;
+        for entry in &self.rules.fleet {
+            let placed = self.count_of(&entry.class);
+            if placed < entry.count {
+                return Err(PlacementError::MissingClass {
+                    class: entry.class.clone(),
+                    expected: entry.count,
+                    placed,
+                });
+            }
         }
 
         // Check no ships overlap
         for (i, ship_i) in self.ships.iter().enumerate() {
             for ship_j in self.ships.iter().skip(i + 1) {
                 if ship_i.intersects(ship_j) {
-                    return false;
+                    return Err(PlacementError::Overlap {
+                        a: ship_j.clone(),
+                        b: ship_i.clone(),
+                    });
                 }
             }
         }
 
-        true
+        Ok(())
     }
 
-    pub fn add_ship(&mut self, new_ship: Ship) -> bool {
-        if !new_ship.in_bounds() {
-            return false;
+    pub fn add_ship(&mut self, new_ship: Ship) -> Result<(), PlacementError> {
+        let allowed = self.rules.count_of(&new_ship.class);
+        if allowed == 0 {
+            return Err(PlacementError::UnknownClass(new_ship));
+        }
+        if !new_ship.in_bounds(&self.rules) {
+            return Err(PlacementError::OutOfBounds(new_ship));
+        }
+        if self.count_of(&new_ship.class) >= allowed {
+            return Err(PlacementError::DuplicateClass(new_ship));
         }
 
-        for ship in &self.ships {
-            if ship.class == new_ship.class || ship.intersects(&new_ship) {
-                return false;
-            }
+        if let Some(ship) = self.ships.iter().find(|ship| ship.intersects(&new_ship)) {
+            return Err(PlacementError::Overlap {
+                a: new_ship,
+                b: ship.clone(),
+            });
         }
 
         self.ships.push(new_ship);
-        true
+        Ok(())
     }
 
-    pub fn apply_shot(&mut self, shot: Position) -> HitType {
+    /// How many ships of `class` have been placed
+    pub fn count_of(&self, class: &ShipClass) -> u32 {
+        self.ships.iter().filter(|ship| ship.class == *class).count() as u32
+    }
+
+    pub fn apply_shot(&mut self, shot: Position) -> Result<HitType, ShotError> {
+        if !shot.in_bounds(&self.rules) {
+            return Err(ShotError::OutOfBounds(shot));
+        }
+        if self.shots.contains(&shot) {
+            return Ok(HitType::Repeat);
+        }
+        self.shots.push(shot);
+
         for ship in &mut self.ships {
             let hit = ship.apply_shot(shot);
             if matches!(hit, HitType::Hit | HitType::Sunk(_)) {
-                return hit;
+                return Ok(hit);
             }
         }
-        HitType::Miss
+        Ok(HitType::Miss)
+    }
+
+    /// Apply a whole salvo in order; a cell repeated within the salvo counts as `Repeat`
+    pub fn apply_salvo(&mut self, shots: &[Position]) -> Result<Vec<HitType>, ShotError> {
+        if let Some(&shot) = shots.iter().find(|shot| !shot.in_bounds(&self.rules)) {
+            return Err(ShotError::OutOfBounds(shot));
+        }
+        shots.iter().map(|&shot| self.apply_shot(shot)).collect()
+    }
+
+    pub fn all_sunk(&self) -> bool {
+        self.ships
+            .iter()
+            .all(|ship| ship.hit_mask == ship.class.sunk_mask())
+    }
+
+    /// Number of ships not yet sunk, which is the salvo size this player may fire
+    pub fn ships_afloat(&self) -> u32 {
+        self.ships
+            .iter()
+            .filter(|ship| ship.hit_mask != ship.class.sunk_mask())
+            .count() as u32
     }
 
     pub fn commit(&self) -> Digest {
         let bytes = bincode::serialize(self).expect("serialization should succeed");
         *risc0_zkvm::sha::Impl::hash_bytes(&bytes)
     }
-}
 
-#[cfg(feature = "rand")]
-impl Distribution<GameState> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> GameState {
-        let mut positions: Vec<Position> = (0..BOARD_SIZE)
-            .flat_map(|x| (0..BOARD_SIZE).map(move |y| Position::new(x as u32, y as u32)))
-            .collect();
-        positions.shuffle(rng);
-
-        let mut state = GameState::new(rng.gen());
-        
-        'outer: for &ship_class in ShipClass::list() {
-            for &pos in &positions {
-                for dir in [Direction::Horizontal, Direction::Vertical] {
-                    if state.add_ship(Ship::new(ship_class, pos, dir)) {
-                        continue 'outer;
+    /// Place the fleet for `rules` at random, or `None` if the fleet is too
+    /// crowded to place after repeated attempts
+    #[cfg(feature = "rand")]
+    pub fn random<R: Rng + ?Sized>(rules: &GameRules, rng: &mut R) -> Option<GameState> {
+        let mut positions: Vec<Position> = rules.positions().collect();
+
+        'attempt: for _ in 0..100 {
+            positions.shuffle(rng);
+            let mut state = GameState::new(rules.clone(), rng.gen());
+
+            'outer: for ship_class in rules.ships_to_place() {
+                for &pos in &positions {
+                    let dir: Direction = rng.gen();
+                    for dir in [dir, dir.flip()] {
+                        if state.add_ship(Ship::new(ship_class.clone(), pos, dir)).is_ok() {
+                            continue 'outer;
+                        }
                     }
                 }
+                continue 'attempt;
             }
-            panic!("Failed to place {:?}", ship_class);
+
+            assert_eq!(state.check(), Ok(()));
+            return Some(state);
         }
+        None
+    }
+}
 
-        assert!(state.check());
-        state
+#[cfg(feature = "rand")]
+impl Distribution<GameState> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> GameState {
+        GameState::random(&GameRules::classic(), rng).expect("the classic fleet always fits")
     }
 }
 
@@ -289,22 +508,300 @@ pub enum HitType {
     Miss,
     Hit,
     Sunk(ShipClass),
+    /// The cell was already fired at; the board is left unchanged
+    Repeat,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShotError {
+    OutOfBounds(Position),
+}
+
+impl Display for ShotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfBounds(pos) => write!(f, "shot at {} is outside the board", pos),
+        }
+    }
+}
+
+impl std::error::Error for ShotError {}
+
+/// Why a ship cannot be added to a board, or a board is not a valid fleet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlacementError {
+    /// The rules themselves cannot be played
+    InvalidRules,
+    /// The board has been fired at, so it cannot be committed as a fresh fleet
+    AlreadyShot,
+    /// The ship runs off the board
+    OutOfBounds(Ship),
+    /// The ship's class is not in the rules' fleet
+    UnknownClass(Ship),
+    /// The ship is one more of its class than the rules allow
+    DuplicateClass(Ship),
+    /// Fewer ships of the class are placed than the rules ask for
+    MissingClass {
+        class: ShipClass,
+        expected: u32,
+        placed: u32,
+    },
+    /// Ship `a` shares a cell with ship `b`, which was placed before it
+    Overlap { a: Ship, b: Ship },
+}
+
+impl Display for PlacementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidRules => write!(f, "the rules cannot be played"),
+            Self::AlreadyShot => write!(f, "the board has already been fired at"),
+            Self::OutOfBounds(ship) => {
+                write!(f, "the {} at {} runs off the board", ship.class, ship.pos)
+            }
+            Self::UnknownClass(ship) => {
+                write!(f, "the {} at {} is not a class the rules know", ship.class, ship.pos)
+            }
+            Self::DuplicateClass(ship) => write!(
+                f,
+                "the {} at {} is one more than the rules allow",
+                ship.class, ship.pos
+            ),
+            Self::MissingClass {
+                class,
+                expected,
+                placed,
+            } => write!(
+                f,
+                "the rules ask for {} {}, the board has {}",
+                expected, class, placed
+            ),
+            Self::Overlap { a, b } => write!(
+                f,
+                "the {} at {} overlaps the {} at {}",
+                a.class, a.pos, b.class, b.pos
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PlacementError {}
+
+/// Derive the session identifier both players agree on from their handshake nonces.
+///
+/// `first` is the nonce of the player who moves first, so both sides hash the
+/// nonces in the same order.
+pub fn session_id(first: &[u8; 16], second: &[u8; 16]) -> Digest {
+    let mut bytes = [0u8; 32];
+    bytes[..16].copy_from_slice(first);
+    bytes[16..].copy_from_slice(second);
+    *risc0_zkvm::sha::Impl::hash_bytes(&bytes)
+}
+
+/// Input to the init and fleet-sunk guests
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BoardInput {
+    pub session: Digest,
+    pub state: GameState,
+}
+
+/// Journal of the init and fleet-sunk guests
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BoardCommit {
+    pub session: Digest,
+    /// Rules the board was checked against, so both players provably play the same variant
+    pub rules: GameRules,
+    pub state: Digest,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct RoundInput {
+    pub session: Digest,
+    pub turn: u32,
     pub state: GameState,
     pub shot: Position,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct RoundCommit {
+    pub session: Digest,
+    /// Index of this shot against the board, starting at zero
+    pub turn: u32,
     pub old_state: Digest,
     pub new_state: Digest,
     pub shot: Position,
     pub hit: HitType,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SalvoInput {
+    pub session: Digest,
+    pub turn: u32,
+    pub state: GameState,
+    pub shots: Vec<Position>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SalvoCommit {
+    pub session: Digest,
+    /// Index of this salvo against the board, starting at zero
+    pub turn: u32,
+    pub old_state: Digest,
+    pub new_state: Digest,
+    pub shots: Vec<Position>,
+    pub hits: Vec<HitType>,
+    /// Ships still afloat after the salvo, i.e. the size of this player's next salvo
+    pub ships_afloat: u32,
+}
+
+// ============================================================================
+// Post-Game Audit
+// ============================================================================
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuditError {
+    /// The revealed board does not pass `GameState::check`
+    InvalidBoard(PlacementError),
+    /// The revealed board does not hash to the commitment from the handshake
+    CommitmentMismatch { expected: Digest, actual: Digest },
+    /// A proven round fired at a cell that cannot be shot
+    InvalidShot { round: usize, error: ShotError },
+    /// Replaying the salvo does not reproduce the proven commit, though every
+    /// individual shot result matches
+    SalvoMismatch { round: usize },
+    /// Replaying the shot does not reproduce the commit that was proven
+    RoundMismatch {
+        round: usize,
+        shot: Position,
+        replayed: HitType,
+        proven: HitType,
+    },
+}
+
+impl Display for AuditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidBoard(error) => write!(f, "revealed board is not a valid fleet: {}", error),
+            Self::CommitmentMismatch { expected, actual } => write!(
+                f,
+                "revealed board commits to {:?}, expected {:?}",
+                actual, expected
+            ),
+            Self::InvalidShot { round, error } => write!(f, "round {}: {}", round, error),
+            Self::SalvoMismatch { round } => {
+                write!(f, "salvo {} does not replay to the proven commit", round)
+            }
+            Self::RoundMismatch {
+                round,
+                shot,
+                replayed,
+                proven,
+            } => write!(
+                f,
+                "round {} at {} does not replay to the proven commit (replayed {:?}, proven {:?})",
+                round, shot, replayed, proven
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+/// Replay every proven round against a revealed initial board.
+///
+/// `commitment` is the board commitment received during the handshake and
+/// `rounds` are the `RoundCommit`s verified during the game, in order. On
+/// success the final state after all shots is returned.
+pub fn audit_game(
+    revealed: &GameState,
+    commitment: &Digest,
+    session: &Digest,
+    rounds: &[RoundCommit],
+) -> Result<GameState, AuditError> {
+    let mut state = check_reveal(revealed, commitment)?;
+    for (round, actual) in rounds.iter().enumerate() {
+        let old_state = state.commit();
+        let hit = state
+            .apply_shot(actual.shot)
+            .map_err(|error| AuditError::InvalidShot { round, error })?;
+        let expected = RoundCommit {
+            session: *session,
+            turn: round as u32,
+            old_state,
+            new_state: state.commit(),
+            shot: actual.shot,
+            hit,
+        };
+        if expected != *actual {
+            return Err(AuditError::RoundMismatch {
+                round,
+                shot: actual.shot,
+                replayed: expected.hit,
+                proven: actual.hit.clone(),
+            });
+        }
+    }
+
+    Ok(state)
+}
+
+/// Replay every proven salvo against a revealed initial board, like `audit_game`
+pub fn audit_salvo_game(
+    revealed: &GameState,
+    commitment: &Digest,
+    session: &Digest,
+    salvos: &[SalvoCommit],
+) -> Result<GameState, AuditError> {
+    let mut state = check_reveal(revealed, commitment)?;
+    for (round, actual) in salvos.iter().enumerate() {
+        let old_state = state.commit();
+        let hits = state
+            .apply_salvo(&actual.shots)
+            .map_err(|error| AuditError::InvalidShot { round, error })?;
+        let expected = SalvoCommit {
+            session: *session,
+            turn: round as u32,
+            old_state,
+            new_state: state.commit(),
+            shots: actual.shots.clone(),
+            hits,
+            ships_afloat: state.ships_afloat(),
+        };
+        if expected != *actual {
+            // Point at the first shot whose result differs, if any
+            let differs = expected
+                .hits
+                .iter()
+                .zip(&actual.hits)
+                .position(|(replayed, proven)| replayed != proven);
+            return Err(match differs {
+                Some(i) => AuditError::RoundMismatch {
+                    round,
+                    shot: actual.shots[i],
+                    replayed: expected.hits[i].clone(),
+                    proven: actual.hits[i].clone(),
+                },
+                None => AuditError::SalvoMismatch { round },
+            });
+        }
+    }
+
+    Ok(state)
+}
+
+fn check_reveal(revealed: &GameState, commitment: &Digest) -> Result<GameState, AuditError> {
+    revealed.check().map_err(AuditError::InvalidBoard)?;
+
+    let actual = revealed.commit();
+    if actual != *commitment {
+        return Err(AuditError::CommitmentMismatch {
+            expected: *commitment,
+            actual,
+        });
+    }
+
+    Ok(revealed.clone())
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -313,19 +810,296 @@ pub struct RoundCommit {
 mod tests {
     use super::*;
 
+    fn classic_class(name: &str) -> ShipClass {
+        GameRules::classic().class(name).unwrap().clone()
+    }
+
+    fn classic_ship(name: &str, pos: (u32, u32), dir: Direction) -> Ship {
+        Ship::new(classic_class(name), pos, dir)
+    }
+
     #[test]
     fn test_basic_board() {
         let state = GameState {
+            rules: GameRules::classic(),
             ships: vec![
-                Ship::new(ShipClass::Carrier, (2, 3), Direction::Vertical),
-                Ship::new(ShipClass::Battleship, (3, 1), Direction::Horizontal),
-                Ship::new(ShipClass::Cruiser, (4, 7), Direction::Vertical),
-                Ship::new(ShipClass::Submarine, (7, 5), Direction::Horizontal),
-                Ship::new(ShipClass::Destroyer, (7, 7), Direction::Horizontal),
+                classic_ship("Carrier", (2, 3), Direction::Vertical),
+                classic_ship("Battleship", (3, 1), Direction::Horizontal),
+                classic_ship("Cruiser", (4, 7), Direction::Vertical),
+                classic_ship("Submarine", (7, 5), Direction::Horizontal),
+                classic_ship("Destroyer", (7, 7), Direction::Horizontal),
             ],
+            shots: Vec::new(),
             pepper: [0; 16],
         };
-        assert!(state.check());
+        assert_eq!(state.check(), Ok(()));
+    }
+
+    fn replay(state: &GameState, session: &Digest, shots: &[Position]) -> Vec<RoundCommit> {
+        let mut state = state.clone();
+        shots
+            .iter()
+            .enumerate()
+            .map(|(turn, &shot)| {
+                let old_state = state.commit();
+                let hit = state.apply_shot(shot).unwrap();
+                RoundCommit {
+                    session: *session,
+                    turn: turn as u32,
+                    old_state,
+                    new_state: state.commit(),
+                    shot,
+                    hit,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_audit_game() {
+        let state = GameState {
+            rules: GameRules::classic(),
+            ships: vec![
+                classic_ship("Carrier", (2, 3), Direction::Vertical),
+                classic_ship("Battleship", (3, 1), Direction::Horizontal),
+                classic_ship("Cruiser", (4, 7), Direction::Vertical),
+                classic_ship("Submarine", (7, 5), Direction::Horizontal),
+                classic_ship("Destroyer", (7, 7), Direction::Horizontal),
+            ],
+            shots: Vec::new(),
+            pepper: [7; 16],
+        };
+        let commitment = state.commit();
+        let session = session_id(&[1; 16], &[2; 16]);
+        let shots = [Position::new(0, 0), Position::new(7, 7), Position::new(8, 7)];
+        let mut rounds = replay(&state, &session, &shots);
+
+        let last = audit_game(&state, &commitment, &session, &rounds).unwrap();
+        assert_eq!(rounds.last().unwrap().hit, HitType::Sunk(classic_class("Destroyer")));
+        assert_eq!(last.commit(), rounds.last().unwrap().new_state);
+
+        let mut other = state.clone();
+        other.pepper = [8; 16];
+        assert!(matches!(
+            audit_game(&other, &commitment, &session, &rounds),
+            Err(AuditError::CommitmentMismatch { .. })
+        ));
+
+        // Rounds proven for another game do not replay
+        let other_session = session_id(&[2; 16], &[1; 16]);
+        assert_ne!(session, other_session);
+        assert!(matches!(
+            audit_game(&state, &commitment, &other_session, &rounds),
+            Err(AuditError::RoundMismatch { round: 0, .. })
+        ));
+
+        rounds[1].hit = HitType::Miss;
+        assert!(matches!(
+            audit_game(&state, &commitment, &session, &rounds),
+            Err(AuditError::RoundMismatch { round: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_all_sunk() {
+        let mut state = GameState {
+            rules: GameRules::classic(),
+            ships: vec![
+                classic_ship("Carrier", (0, 0), Direction::Horizontal),
+                classic_ship("Battleship", (0, 1), Direction::Horizontal),
+                classic_ship("Cruiser", (0, 2), Direction::Horizontal),
+                classic_ship("Submarine", (0, 3), Direction::Horizontal),
+                classic_ship("Destroyer", (0, 4), Direction::Horizontal),
+            ],
+            shots: Vec::new(),
+            pepper: [0; 16],
+        };
+        assert!(!state.all_sunk());
+
+        let cells: Vec<Position> = state.ships.iter().flat_map(|s| s.points()).collect();
+        let (last, rest) = cells.split_last().unwrap();
+        for &pos in rest {
+            state.apply_shot(pos).unwrap();
+        }
+        assert!(!state.all_sunk());
+        assert_eq!(state.apply_shot(*last), Ok(HitType::Sunk(classic_class("Destroyer"))));
+        assert!(state.all_sunk());
+
+        // A board that starts damaged is not a valid commitment
+        assert_eq!(state.check(), Err(PlacementError::AlreadyShot));
+    }
+
+    #[test]
+    fn test_invalid_shots() {
+        let mut state = GameState::new(GameRules::classic(), [0; 16]);
+        state
+            .add_ship(classic_ship("Destroyer", (0, 0), Direction::Horizontal))
+            .unwrap();
+
+        assert_eq!(
+            state.apply_shot(Position::new(50, 50)),
+            Err(ShotError::OutOfBounds(Position::new(50, 50)))
+        );
+        assert_eq!(state.apply_shot(Position::new(0, 0)), Ok(HitType::Hit));
+        assert_eq!(
+            state.apply_shot(Position::new(1, 0)),
+            Ok(HitType::Sunk(classic_class("Destroyer")))
+        );
+
+        // Firing at the last cell again must not report a second sinking
+        let before = state.commit();
+        assert_eq!(state.apply_shot(Position::new(1, 0)), Ok(HitType::Repeat));
+        assert_eq!(state.commit(), before);
+        assert_eq!(state.shots, vec![Position::new(0, 0), Position::new(1, 0)]);
+    }
+
+    fn small_rules() -> GameRules {
+        GameRules {
+            width: 6,
+            height: 4,
+            fleet: vec![
+                FleetEntry {
+                    class: ShipClass::new("Frigate", 'F', 3),
+                    count: 1,
+                },
+                FleetEntry {
+                    class: ShipClass::new("Patrol", 'P', 2),
+                    count: 2,
+                },
+            ],
+            mode: GameMode::Salvo,
+        }
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let rules = small_rules();
+        assert!(rules.check());
+        assert_eq!(rules.total_ships(), 3);
+
+        let frigate = rules.class("Frigate").unwrap().clone();
+        let patrol = rules.class("Patrol").unwrap().clone();
+        let mut state = GameState::new(rules.clone(), [0; 16]);
+        let first = Ship::new(frigate.clone(), (3, 0), Direction::Horizontal);
+        assert_eq!(state.add_ship(first.clone()), Ok(()));
+        let off_board = Ship::new(patrol.clone(), (5, 1), Direction::Horizontal);
+        assert_eq!(
+            state.add_ship(off_board.clone()),
+            Err(PlacementError::OutOfBounds(off_board))
+        );
+        let second = Ship::new(frigate, (0, 0), Direction::Vertical);
+        assert_eq!(
+            state.add_ship(second.clone()),
+            Err(PlacementError::DuplicateClass(second))
+        );
+        let stranger = Ship::new(ShipClass::new("Frigate", 'F', 2), (0, 0), Direction::Vertical);
+        assert_eq!(
+            state.add_ship(stranger.clone()),
+            Err(PlacementError::UnknownClass(stranger.clone()))
+        );
+        let mut smuggled = state.clone();
+        smuggled.ships.push(stranger.clone());
+        assert_eq!(smuggled.check(), Err(PlacementError::UnknownClass(stranger)));
+        // A span of 0 is refused by class before its bounds are worked out
+        let empty = Ship::new(ShipClass::new("Raft", 'R', 0), (0, 0), Direction::Horizontal);
+        assert_eq!(
+            state.add_ship(empty.clone()),
+            Err(PlacementError::UnknownClass(empty.clone()))
+        );
+        let mut smuggled = state.clone();
+        smuggled.ships.push(empty.clone());
+        assert_eq!(smuggled.check(), Err(PlacementError::UnknownClass(empty)));
+        let crossing = Ship::new(patrol.clone(), (4, 0), Direction::Vertical);
+        assert_eq!(
+            state.add_ship(crossing.clone()),
+            Err(PlacementError::Overlap { a: crossing, b: first })
+        );
+        let placed = state.add_ship(Ship::new(patrol.clone(), (0, 3), Direction::Horizontal));
+        assert_eq!(placed, Ok(()));
+        assert_eq!(
+            state.check(),
+            Err(PlacementError::MissingClass {
+                class: patrol.clone(),
+                expected: 2,
+                placed: 1
+            })
+        );
+        let placed = state.add_ship(Ship::new(patrol.clone(), (0, 0), Direction::Vertical));
+        assert_eq!(placed, Ok(()));
+        assert!(state.add_ship(Ship::new(patrol, (2, 2), Direction::Horizontal)).is_err());
+        assert_eq!(state.check(), Ok(()));
+
+        assert_eq!(
+            state.apply_shot(Position::new(6, 0)),
+            Err(ShotError::OutOfBounds(Position::new(6, 0)))
+        );
+
+        let mut too_long = rules.clone();
+        too_long.fleet[0].class.span = MAX_SHIP_SPAN + 1;
+        assert!(!too_long.check());
+
+        let mut duplicate = rules.clone();
+        duplicate.fleet[1].class.symbol = 'F';
+        assert!(!duplicate.check());
+
+        // Counts that wrap the cell total around are refused, not allocated
+        let mut overflowing = rules;
+        overflowing.fleet[0].count = u32::MAX / 3 + 1;
+        assert!(!overflowing.check());
+        overflowing.fleet[0].count = 1;
+        overflowing.fleet[1].count = u32::MAX / 2;
+        overflowing.fleet.push(overflowing.fleet[1].clone());
+        overflowing.fleet[2].class = ShipClass::new("Sloop", 'S', 2);
+        assert!(!overflowing.check());
+    }
+
+    #[test]
+    fn test_salvo() {
+        let rules = small_rules();
+        let frigate = rules.class("Frigate").unwrap().clone();
+        let patrol = rules.class("Patrol").unwrap().clone();
+        let mut state = GameState::new(rules, [3; 16]);
+        state.add_ship(Ship::new(frigate, (3, 0), Direction::Horizontal)).unwrap();
+        state.add_ship(Ship::new(patrol.clone(), (0, 3), Direction::Horizontal)).unwrap();
+        state.add_ship(Ship::new(patrol.clone(), (0, 0), Direction::Vertical)).unwrap();
+        assert_eq!(state.check(), Ok(()));
+        assert_eq!(state.ships_afloat(), 3);
+
+        let initial = state.clone();
+        let session = session_id(&[4; 16], &[5; 16]);
+        let shots = vec![Position::new(0, 3), Position::new(1, 3), Position::new(1, 3)];
+
+        let old_state = state.commit();
+        let hits = state.apply_salvo(&shots).unwrap();
+        assert_eq!(
+            hits,
+            vec![HitType::Hit, HitType::Sunk(patrol), HitType::Repeat]
+        );
+        assert_eq!(state.ships_afloat(), 2);
+
+        let mut salvos = vec![SalvoCommit {
+            session,
+            turn: 0,
+            old_state,
+            new_state: state.commit(),
+            shots,
+            hits,
+            ships_afloat: 2,
+        }];
+        audit_salvo_game(&initial, &initial.commit(), &session, &salvos).unwrap();
+
+        salvos[0].ships_afloat = 3;
+        assert_eq!(
+            audit_salvo_game(&initial, &initial.commit(), &session, &salvos),
+            Err(AuditError::SalvoMismatch { round: 0 })
+        );
+
+        // Nothing is applied when any shot in the salvo is off the board
+        let before = state.clone();
+        assert!(state
+            .apply_salvo(&[Position::new(5, 3), Position::new(9, 9)])
+            .is_err());
+        assert_eq!(state, before);
     }
 
     #[test]
@@ -333,7 +1107,10 @@ mod tests {
     fn test_random_boards() {
         for _ in 0..100 {
             let state: GameState = rand::random();
-            assert!(state.check());
+            assert_eq!(state.check(), Ok(()));
+
+            let state = GameState::random(&small_rules(), &mut rand::thread_rng()).unwrap();
+            assert_eq!(state.check(), Ok(()));
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,19 @@
+use battleship_core::{BoardCommit, BoardInput};
+use risc0_zkvm::guest::env;
+
+fn main() {
+    // Read the losing player's current game state and session from the host
+    let BoardInput { session, state } = env::read();
+
+    // Every cell of every ship must have been hit
+    if !state.all_sunk() {
+        panic!("Invalid game over claim: at least one ship is still afloat");
+    }
+
+    // Commit the session, rules and state hash to the journal
+    env::commit(&BoardCommit {
+        session,
+        rules: state.rules.clone(),
+        state: state.commit(),
+    });
+}
@@ -0,0 +1,23 @@
+use battleship_core::history::HistoryInput;
+use risc0_zkvm::guest::env;
+
+fn main() {
+    // Read the step: the image IDs to trust and the journals it builds on
+    let input: HistoryInput = env::read();
+
+    // Each journal only counts once a receipt for it has been verified; the
+    // prover supplies those receipts as assumptions
+    for (image_id, journal) in input.assumptions() {
+        if env::verify(image_id, &journal).is_err() {
+            panic!("Missing receipt for image {}", image_id);
+        }
+    }
+
+    // The result must continue the history from where it left off
+    let history = match input.apply() {
+        Ok(history) => history,
+        Err(e) => panic!("Invalid history step: {}", e),
+    };
+
+    env::commit(&history);
+}
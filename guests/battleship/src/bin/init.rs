@@ -1,15 +1,19 @@
-use battleship_core::GameState;
+use battleship_core::{BoardCommit, BoardInput};
 use risc0_zkvm::guest::env;
 
 fn main() {
-    // Read the initial game state from the host
-    let state: GameState = env::read();
+    // Read the initial game state and session from the host
+    let BoardInput { session, state } = env::read();
 
-    // Validate the board setup
-    if !state.check() {
-        panic!("Invalid game state: ships overlap or are out of bounds");
+    // Validate the board setup; a bad fleet aborts the proof with the reason
+    if let Err(error) = state.check() {
+        panic!("Invalid game state: {}", error);
     }
 
-    // Commit the state hash to the journal
-    env::commit(&state.commit());
-}
\ No newline at end of file
+    // Commit the session, rules and state hash to the journal
+    env::commit(&BoardCommit {
+        session,
+        rules: state.rules.clone(),
+        state: state.commit(),
+    });
+}
@@ -2,23 +2,33 @@ use battleship_core::{RoundCommit, RoundInput};
 use risc0_zkvm::guest::env;
 
 fn main() {
-    // Read the round input (current state + shot position)
-    let RoundInput { mut state, shot } = env::read();
+    // Read the round input (session + turn + current state + shot position)
+    let RoundInput {
+        session,
+        turn,
+        mut state,
+        shot,
+    } = env::read();
 
     // Create commitment to old state
     let old_state = state.commit();
 
-    // Apply the shot and get the result
-    let hit = state.apply_shot(shot);
+    // Apply the shot and get the result; out-of-bounds shots cannot be proven
+    let hit = match state.apply_shot(shot) {
+        Ok(hit) => hit,
+        Err(e) => panic!("Invalid shot: {}", e),
+    };
 
     // Create commitment to new state
     let new_state = state.commit();
 
     // Write the proof to the journal
     env::commit(&RoundCommit {
+        session,
+        turn,
         old_state,
         new_state,
         shot,
         hit,
     });
-}
\ No newline at end of file
+}
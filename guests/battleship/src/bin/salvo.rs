@@ -0,0 +1,35 @@
+use battleship_core::{SalvoCommit, SalvoInput};
+use risc0_zkvm::guest::env;
+
+fn main() {
+    // Read the salvo input (session + turn + current state + every shot fired)
+    let SalvoInput {
+        session,
+        turn,
+        mut state,
+        shots,
+    } = env::read();
+
+    // Create commitment to old state
+    let old_state = state.commit();
+
+    // Apply every shot in order; a salvo with any out-of-bounds shot cannot be proven
+    let hits = match state.apply_salvo(&shots) {
+        Ok(hits) => hits,
+        Err(e) => panic!("Invalid salvo: {}", e),
+    };
+
+    // Create commitment to new state
+    let new_state = state.commit();
+
+    // Write the proof to the journal, including the size of the next salvo
+    env::commit(&SalvoCommit {
+        session,
+        turn,
+        old_state,
+        new_state,
+        shots,
+        hits,
+        ships_afloat: state.ships_afloat(),
+    });
+}
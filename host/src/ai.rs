@@ -0,0 +1,394 @@
+use crate::board_display::BoardDisplay;
+use battleship_core::{Direction, GameRules, GameState, Position, Ship};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use anyhow::Context;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::path::Path;
+
+/// How hard the computer opponent plays
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Difficulty {
+    /// Fires at random cells
+    Easy,
+    /// Hunts on a checkerboard, then finishes off ships it has hit
+    Medium,
+    /// Fires where the remaining fleet is most likely to be
+    Hard,
+}
+
+impl Difficulty {
+    pub fn strategy(self) -> Box<dyn ShotStrategy + Send> {
+        match self {
+            Self::Easy => Box::new(RandomShots::default()),
+            Self::Medium => Box::new(HuntTarget::default()),
+            Self::Hard => Box::new(ProbabilityDensity::default()),
+        }
+    }
+
+    pub fn placement(self) -> Placement {
+        match self {
+            Self::Easy => Placement::Uniform,
+            Self::Medium => Placement::Spread,
+            Self::Hard => Placement::Edges,
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Easy => write!(f, "easy"),
+            Self::Medium => write!(f, "medium"),
+            Self::Hard => write!(f, "hard"),
+        }
+    }
+}
+
+/// Picks where the computer fires next, from what it knows of the opponent's board
+pub trait ShotStrategy {
+    /// Choose an unfired cell that is not already in `pending`, the shots
+    /// picked so far for the current salvo
+    fn next_shot(&mut self, board: &BoardDisplay, pending: &[Position]) -> Position;
+}
+
+/// Cells we have not fired at and are not about to
+fn open_cells(board: &BoardDisplay, pending: &[Position]) -> Vec<Position> {
+    board
+        .rules()
+        .positions()
+        .filter(|pos| !board.already_shot(*pos) && !pending.contains(pos))
+        .collect()
+}
+
+/// Hits that do not belong to a ship we have already sunk
+fn open_hits(board: &BoardDisplay) -> usize {
+    let sunk: u32 = board.sunk_ships().iter().map(|class| class.span).sum();
+    board.hit_count().saturating_sub(sunk as usize)
+}
+
+fn offset(pos: Position, dx: i32, dy: i32, rules: &GameRules) -> Option<Position> {
+    let x = pos.x.checked_add_signed(dx)?;
+    let y = pos.y.checked_add_signed(dy)?;
+    Some(Position::new(x, y)).filter(|pos| pos.in_bounds(rules))
+}
+
+const NEIGHBOURS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+fn pick_any(rng: &mut StdRng, board: &BoardDisplay, pending: &[Position]) -> Position {
+    *open_cells(board, pending)
+        .choose(rng)
+        .expect("the game ends before every cell is fired at")
+}
+
+/// Uniformly random shots
+pub struct RandomShots {
+    rng: StdRng,
+}
+
+impl Default for RandomShots {
+    fn default() -> Self {
+        Self {
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl ShotStrategy for RandomShots {
+    fn next_shot(&mut self, board: &BoardDisplay, pending: &[Position]) -> Position {
+        pick_any(&mut self.rng, board, pending)
+    }
+}
+
+/// Shots read from a script, one `x,y` per line; cells already fired at are
+/// skipped, and once the script runs out the shots are random
+pub struct ScriptedShots {
+    script: VecDeque<Position>,
+    fallback: RandomShots,
+}
+
+impl ScriptedShots {
+    pub fn new(script: Vec<Position>) -> Self {
+        Self {
+            script: script.into(),
+            fallback: RandomShots::default(),
+        }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read shots from {}", path.display()))?;
+
+        let script = contents
+            .lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(i, line)| {
+                let parsed = line
+                    .split_once(',')
+                    .and_then(|(x, y)| Some(Position::new(x.trim().parse().ok()?, y.trim().parse().ok()?)));
+                parsed.ok_or_else(|| anyhow::anyhow!("{}:{}: expected x,y", path.display(), i + 1))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self::new(script))
+    }
+}
+
+impl ShotStrategy for ScriptedShots {
+    fn next_shot(&mut self, board: &BoardDisplay, pending: &[Position]) -> Position {
+        while let Some(pos) = self.script.pop_front() {
+            if pos.in_bounds(board.rules()) && !board.already_shot(pos) && !pending.contains(&pos) {
+                return pos;
+            }
+        }
+        self.fallback.next_shot(board, pending)
+    }
+}
+
+/// Checkerboard search until something is hit, then work outward from the
+/// hits, preferring cells that extend a line of hits
+pub struct HuntTarget {
+    rng: StdRng,
+}
+
+impl Default for HuntTarget {
+    fn default() -> Self {
+        Self {
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl HuntTarget {
+    fn target(&mut self, board: &BoardDisplay, pending: &[Position]) -> Option<Position> {
+        let rules = board.rules();
+        let mut best: Vec<Position> = Vec::new();
+        let mut best_score = 0;
+
+        for hit in rules.positions().filter(|pos| board.is_hit(*pos)) {
+            for (dx, dy) in NEIGHBOURS {
+                let Some(cell) = offset(hit, dx, dy, rules) else {
+                    continue;
+                };
+                if board.already_shot(cell) || pending.contains(&cell) {
+                    continue;
+                }
+
+                let in_line = offset(hit, -dx, -dy, rules).is_some_and(|back| board.is_hit(back));
+                let score = if in_line { 2 } else { 1 };
+                if score > best_score {
+                    best_score = score;
+                    best.clear();
+                }
+                if score == best_score {
+                    best.push(cell);
+                }
+            }
+        }
+
+        best.choose(&mut self.rng).copied()
+    }
+
+    fn hunt(&mut self, board: &BoardDisplay, pending: &[Position]) -> Position {
+        let parity: Vec<Position> = open_cells(board, pending)
+            .into_iter()
+            .filter(|pos| (pos.x + pos.y) % 2 == 0)
+            .collect();
+
+        match parity.choose(&mut self.rng) {
+            Some(pos) => *pos,
+            None => pick_any(&mut self.rng, board, pending),
+        }
+    }
+}
+
+impl ShotStrategy for HuntTarget {
+    fn next_shot(&mut self, board: &BoardDisplay, pending: &[Position]) -> Position {
+        if open_hits(board) > 0 {
+            if let Some(pos) = self.target(board, pending) {
+                return pos;
+            }
+        }
+        self.hunt(board, pending)
+    }
+}
+
+/// Fire at the cell covered by the most placements of the ships still afloat,
+/// weighting placements through unresolved hits heavily
+pub struct ProbabilityDensity {
+    rng: StdRng,
+}
+
+impl Default for ProbabilityDensity {
+    fn default() -> Self {
+        Self {
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl ProbabilityDensity {
+    fn heat_map(board: &BoardDisplay, pending: &[Position]) -> Vec<(Position, u64)> {
+        let rules = board.rules();
+        // Without unresolved hits every hit belongs to a sunk ship and blocks placements
+        let hits_open = open_hits(board) > 0;
+        let mut heat = vec![0u64; (rules.width * rules.height) as usize];
+
+        for entry in &rules.fleet {
+            let sunk = board
+                .sunk_ships()
+                .iter()
+                .filter(|class| class.name == entry.class.name)
+                .count() as u32;
+            let afloat = entry.count.saturating_sub(sunk) as u64;
+            if afloat == 0 {
+                continue;
+            }
+
+            for pos in rules.positions() {
+                for dir in [Direction::Horizontal, Direction::Vertical] {
+                    let ship = Ship::new(entry.class.clone(), pos, dir);
+                    if !ship.in_bounds(rules) {
+                        continue;
+                    }
+
+                    let mut covered_hits = 0;
+                    let blocked = ship.points().any(|cell| {
+                        if board.is_hit(cell) {
+                            covered_hits += 1;
+                            !hits_open
+                        } else {
+                            board.already_shot(cell)
+                        }
+                    });
+                    if blocked {
+                        continue;
+                    }
+
+                    let weight = afloat * if covered_hits > 0 { 100 * covered_hits } else { 1 };
+                    for cell in ship.points() {
+                        if !board.already_shot(cell) && !pending.contains(&cell) {
+                            heat[(cell.y * rules.width + cell.x) as usize] += weight;
+                        }
+                    }
+                }
+            }
+        }
+
+        rules
+            .positions()
+            .map(|pos| (pos, heat[(pos.y * rules.width + pos.x) as usize]))
+            .collect()
+    }
+}
+
+impl ShotStrategy for ProbabilityDensity {
+    fn next_shot(&mut self, board: &BoardDisplay, pending: &[Position]) -> Position {
+        let heat = Self::heat_map(board, pending);
+        let hottest = heat.iter().map(|(_, weight)| *weight).max().unwrap_or(0);
+        if hottest == 0 {
+            return pick_any(&mut self.rng, board, pending);
+        }
+
+        let candidates: Vec<Position> = heat
+            .into_iter()
+            .filter(|(_, weight)| *weight == hottest)
+            .map(|(pos, _)| pos)
+            .collect();
+        *candidates.choose(&mut self.rng).expect("at least one cell is hottest")
+    }
+}
+
+/// The cells an opponent hunting with the heat map is most likely to fire at
+/// next, hottest first, judged from the shots already fired at `board`
+pub fn likely_shots(board: &BoardDisplay, count: usize) -> Vec<Position> {
+    let mut heat = ProbabilityDensity::heat_map(board, &[]);
+    heat.retain(|(_, weight)| *weight > 0);
+    heat.sort_by_key(|(_, weight)| std::cmp::Reverse(*weight));
+    heat.into_iter().take(count).map(|(pos, _)| pos).collect()
+}
+
+/// How the computer lays out its fleet
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// Any valid layout, from the `core` sampler
+    Uniform,
+    /// No two ships touch, not even diagonally
+    Spread,
+    /// Ships hug the edges of the board, where hunting patterns look last
+    Edges,
+}
+
+impl Placement {
+    /// Lay out the fleet for `rules`, falling back to a uniform layout when
+    /// the strategy cannot fit the fleet
+    pub fn place<R: Rng + ?Sized>(self, rules: &GameRules, rng: &mut R) -> anyhow::Result<GameState> {
+        let placed = match self {
+            Self::Uniform => None,
+            Self::Spread => place_weighted(rules, rng, |state, ship| {
+                let touches = ship.points().any(|cell| {
+                    state.ships.iter().flat_map(|other| other.points()).any(|other| {
+                        cell.x.abs_diff(other.x) <= 1 && cell.y.abs_diff(other.y) <= 1
+                    })
+                });
+                (!touches).then_some(1)
+            }),
+            Self::Edges => place_weighted(rules, rng, |_, ship| {
+                let edge_cells = ship
+                    .points()
+                    .filter(|cell| {
+                        cell.x == 0
+                            || cell.y == 0
+                            || cell.x == rules.width - 1
+                            || cell.y == rules.height - 1
+                    })
+                    .count() as u32;
+                Some(1 + 4 * edge_cells)
+            }),
+        };
+
+        placed
+            .or_else(|| GameState::random(rules, rng))
+            .ok_or_else(|| anyhow::anyhow!("The fleet does not fit on the board"))
+    }
+}
+
+/// Place ships largest first, choosing each among the valid spots with the
+/// weight `score` gives it; `None` from `score` rules a spot out
+fn place_weighted<R: Rng + ?Sized>(
+    rules: &GameRules,
+    rng: &mut R,
+    score: impl Fn(&GameState, &Ship) -> Option<u32>,
+) -> Option<GameState> {
+    'attempt: for _ in 0..20 {
+        let mut state = GameState::new(rules.clone(), rng.gen());
+
+        for class in rules.ships_to_place() {
+            let candidates: Vec<(Ship, u32)> = rules
+                .positions()
+                .flat_map(|pos| {
+                    [Direction::Horizontal, Direction::Vertical]
+                        .map(|dir| Ship::new(class.clone(), pos, dir))
+                })
+                .filter_map(|ship| {
+                    let weight = score(&state, &ship)?;
+                    let mut trial = state.clone();
+                    trial.add_ship(ship.clone()).is_ok().then_some((ship, weight))
+                })
+                .collect();
+
+            match candidates.choose_weighted(rng, |(_, weight)| *weight) {
+                Ok((ship, _)) => state
+                    .add_ship(ship.clone())
+                    .expect("candidates were placed on a copy of the board"),
+                Err(_) => continue 'attempt,
+            }
+        }
+
+        if state.check().is_ok() {
+            return Some(state);
+        }
+    }
+    None
+}
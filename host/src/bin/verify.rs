@@ -0,0 +1,22 @@
+//! Offline check of a game transcript, for referees settling a disputed game
+//! without replaying it.
+//!
+//! Usage: `battleship-verify <transcript>`. Receipts are checked under the
+//! verification policy from the environment, as for the game itself.
+
+use battleship_host::transcript::Transcript;
+use battleship_host::verification::VerificationPolicy;
+use std::path::Path;
+
+fn main() -> anyhow::Result<()> {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("Usage: battleship-verify <transcript>");
+        std::process::exit(2);
+    };
+
+    let policy = VerificationPolicy::from_env()?;
+    if !Transcript::load(Path::new(&path))?.print_report(&policy) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -1,18 +1,22 @@
-use battleship_core::{GameState, HitType, Position, ShipClass, BOARD_SIZE};
+use battleship_core::{GameRules, GameState, HitType, Position, ShipClass};
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BoardDisplay {
+    rules: GameRules,
     shots: HashSet<Position>,
     hits: HashSet<Position>,
-    sunk_ships: HashSet<ShipClass>,
+    sunk_ships: Vec<ShipClass>,
 }
 
 impl BoardDisplay {
-    pub fn new() -> Self {
+    pub fn new(rules: GameRules) -> Self {
         Self {
+            rules,
             shots: HashSet::new(),
             hits: HashSet::new(),
-            sunk_ships: HashSet::new(),
+            sunk_ships: Vec::new(),
         }
     }
 
@@ -24,119 +28,120 @@ impl BoardDisplay {
             }
             HitType::Sunk(ship_class) => {
                 self.hits.insert(pos);
-                self.sunk_ships.insert(ship_class);
+                self.sunk_ships.push(ship_class);
             }
-            HitType::Miss => {}
+            HitType::Miss | HitType::Repeat => {}
         }
     }
 
+    pub fn already_shot(&self, pos: Position) -> bool {
+        self.shots.contains(&pos)
+    }
+
+    pub fn rules(&self) -> &GameRules {
+        &self.rules
+    }
+
+    pub fn is_hit(&self, pos: Position) -> bool {
+        self.hits.contains(&pos)
+    }
+
+    pub fn hit_count(&self) -> usize {
+        self.hits.len()
+    }
+
+    pub fn sunk_ships(&self) -> &[ShipClass] {
+        &self.sunk_ships
+    }
+
     /// Display your own board (shows ships)
     pub fn display_own_board(&self, state: &GameState) {
-        println!("\n╔══════════════════════════════════════╗");
-        println!("║       YOUR BOARD (Ships Visible)     ║");
-        println!("╠══════════════════════════════════════╣");
-        
-        // Header
-        print!("║   ");
-        for x in 0..BOARD_SIZE {
-            print!(" {} ", x);
-        }
-        println!("     ║");
-        println!("║   ┌──────────────────────────────┐   ║");
+        self.display_fleet("YOUR BOARD (Ships Visible)", state);
+    }
 
-        // Board rows
-        for y in 0..BOARD_SIZE {
-            print!("║ {} │", y);
-            for x in 0..BOARD_SIZE {
-                let pos = Position::new(x as u32, y as u32);
-                let symbol = self.get_own_board_symbol(pos, state);
-                print!(" {} ", symbol);
-            }
-            println!("│   ║");
-        }
+    /// Display a board with its ships and the shots fired at it
+    pub fn display_fleet(&self, title: &str, state: &GameState) {
+        self.display_grid(title, |pos| self.cell(pos, Some(state)));
 
-        println!("║   └──────────────────────────────┘   ║");
-        println!("╚══════════════════════════════════════╝");
-        
         // Legend
-        println!("\n  Legend: [A]=Carrier [B]=Battleship [C]=Cruiser [S]=Sub [D]=Destroyer");
+        let ships: Vec<String> = self
+            .rules
+            .fleet
+            .iter()
+            .map(|entry| format!("[{}]={}", entry.class.symbol, entry.class.name))
+            .collect();
+        println!("\n  Legend: {}", ships.join(" "));
         println!("          [X]=Hit  [O]=Miss  [~]=Water");
     }
 
     /// Display opponent's board (ships hidden, only shows hits/misses)
     pub fn display_opponent_board(&self) {
-        println!("\n╔══════════════════════════════════════╗");
-        println!("║    OPPONENT BOARD (Ships Hidden)     ║");
-        println!("╠══════════════════════════════════════╣");
-        
-        // Header
-        print!("║   ");
-        for x in 0..BOARD_SIZE {
-            print!(" {} ", x);
-        }
-        println!("     ║");
-        println!("║   ┌──────────────────────────────┐   ║");
+        self.display_shots("OPPONENT BOARD (Ships Hidden)");
+    }
 
-        // Board rows
-        for y in 0..BOARD_SIZE {
-            print!("║ {} │", y);
-            for x in 0..BOARD_SIZE {
-                let pos = Position::new(x as u32, y as u32);
-                let symbol = if self.hits.contains(&pos) {
-                    "X" // Hit (proven by ZK proof)
-                } else if self.shots.contains(&pos) {
-                    "O" // Miss (proven by ZK proof)
-                } else {
-                    "~" // Unknown
-                };
-                print!(" {} ", symbol);
-            }
-            println!("│   ║");
-        }
+    /// Display only the shots fired at a board and their proven results
+    pub fn display_shots(&self, title: &str) {
+        self.display_grid(title, |pos| self.cell(pos, None));
 
-        println!("║   └──────────────────────────────┘   ║");
-        println!("╚══════════════════════════════════════╝");
-        
         // Ships sunk
         if !self.sunk_ships.is_empty() {
             print!("\n  Ships Sunk: ");
             for ship in &self.sunk_ships {
-                print!("{:?} ", ship);
+                print!("{} ", ship);
             }
             println!();
         }
-        
+
         println!("\n  [X]=Hit (ZK Verified)  [O]=Miss (ZK Verified)  [~]=Unknown");
     }
 
-    fn get_own_board_symbol(&self, pos: Position, state: &GameState) -> &str {
-        // Check if there's a ship at this position
-        for ship in &state.ships {
-            if ship.points().any(|p| p == pos) {
-                // Check if this position was hit
-                if self.hits.contains(&pos) {
-                    return "X"; // Hit on your ship
-                }
-                // Return ship symbol
-                return match ship.class {
-                    ShipClass::Carrier => "A",
-                    ShipClass::Battleship => "B",
-                    ShipClass::Cruiser => "C",
-                    ShipClass::Submarine => "S",
-                    ShipClass::Destroyer => "D",
-                };
+    /// Draw a framed grid sized to the rules, one symbol per cell
+    fn display_grid(&self, title: &str, symbol: impl Fn(Position) -> char) {
+        let label = (self.rules.height - 1).to_string().len();
+        let grid = 3 * self.rules.width as usize;
+        let inner = (grid + label + 7).max(title.chars().count() + 4);
+        let pad = " ".repeat(inner - (grid + label + 7));
+
+        println!("\n╔{}╗", "═".repeat(inner));
+        println!("║{:^inner$}║", title);
+        println!("╠{}╣", "═".repeat(inner));
+
+        // Header
+        print!("║{}", " ".repeat(label + 2));
+        for x in 0..self.rules.width {
+            print!("{:^3}", x);
+        }
+        println!("     {}║", pad);
+        println!("║{}┌{}┐   {}║", " ".repeat(label + 2), "─".repeat(grid), pad);
+
+        // Board rows
+        for y in 0..self.rules.height {
+            print!("║ {:>label$} │", y);
+            for x in 0..self.rules.width {
+                print!(" {} ", symbol(Position::new(x, y)));
             }
+            println!("│   {}║", pad);
         }
 
-        // No ship, check if shot
+        println!("║{}└{}┘   {}║", " ".repeat(label + 2), "─".repeat(grid), pad);
+        println!("╚{}╝", "═".repeat(inner));
+    }
+
+    /// The symbol for one cell: `X` hit, `O` miss, the class symbol of an
+    /// untouched ship when the `fleet` is known, otherwise `~`
+    pub fn cell(&self, pos: Position, fleet: Option<&GameState>) -> char {
+        if self.hits.contains(&pos) {
+            return 'X'; // Hit (proven by ZK proof)
+        }
         if self.shots.contains(&pos) {
-            "O" // Miss
-        } else {
-            "~" // Water
+            return 'O'; // Miss (proven by ZK proof)
         }
+        fleet
+            .and_then(|state| state.ships.iter().find(|ship| ship.points().any(|p| p == pos)))
+            .map_or('~', |ship| ship.class.symbol)
     }
 
     pub fn ships_remaining(&self) -> usize {
-        ShipClass::list().len() - self.sunk_ships.len()
+        self.rules.total_ships() - self.sunk_ships.len()
     }
-}
\ No newline at end of file
+}
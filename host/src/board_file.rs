@@ -0,0 +1,310 @@
+use anyhow::Context;
+use battleship_core::{Direction, GameRules, GameState, Position, Ship};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Cells without a ship in the text grid
+const WATER: [char; 2] = ['.', '~'];
+
+/// How a fleet layout is written down, picked from the file extension:
+/// `.json` is JSON and anything else the text grid
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BoardFormat {
+    /// One class symbol per cell, as `display_board` draws it, `.` or `~` for
+    /// water; whitespace between cells and `#` comment lines are ignored:
+    ///
+    /// ```text
+    /// A A A A A . . . . .
+    /// . . . . . . . . . .
+    /// B . . . . . . D D .
+    /// ```
+    ///
+    /// Ships of one class may touch. A run is read rightward when it splits
+    /// into whole ships that way and downward otherwise, so a layout that
+    /// only works the other way round has to be written as JSON.
+    Grid,
+    /// A list of ships placed by class name:
+    ///
+    /// ```json
+    /// { "ships": [{ "class": "Carrier", "x": 0, "y": 0, "dir": "Horizontal" }, ...] }
+    /// ```
+    Json,
+}
+
+impl BoardFormat {
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Grid,
+        }
+    }
+}
+
+/// The JSON layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardFile {
+    pub ships: Vec<ShipPlacement>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShipPlacement {
+    pub class: String,
+    pub x: u32,
+    pub y: u32,
+    pub dir: Direction,
+}
+
+/// Load a fleet for `rules` from a board file. The pepper is always freshly
+/// generated, so a layout reused across games never commits to the same digest.
+pub fn load(path: &Path, rules: &GameRules) -> anyhow::Result<GameState> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read board from {}", path.display()))?;
+    let state = match BoardFormat::for_path(path) {
+        BoardFormat::Grid => parse_grid(&contents, rules),
+        BoardFormat::Json => serde_json::from_str::<BoardFile>(&contents)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| file.to_state(rules)),
+    };
+    state.with_context(|| format!("{} is not a valid board", path.display()))
+}
+
+/// Write the fleet of `state` to a board file; the pepper is left out
+pub fn save(path: &Path, state: &GameState) -> anyhow::Result<()> {
+    let contents = match BoardFormat::for_path(path) {
+        BoardFormat::Grid => to_grid(state),
+        BoardFormat::Json => serde_json::to_string_pretty(&BoardFile::from_state(state))?,
+    };
+    std::fs::write(path, contents)
+        .with_context(|| format!("Failed to write board to {}", path.display()))
+}
+
+impl BoardFile {
+    pub fn from_state(state: &GameState) -> Self {
+        let ships = state
+            .ships
+            .iter()
+            .map(|ship| ShipPlacement {
+                class: ship.class.name.clone(),
+                x: ship.pos.x,
+                y: ship.pos.y,
+                dir: ship.dir,
+            })
+            .collect();
+        Self { ships }
+    }
+
+    pub fn to_state(&self, rules: &GameRules) -> anyhow::Result<GameState> {
+        let mut state = GameState::new(rules.clone(), rand::random());
+
+        for placement in &self.ships {
+            let class = rules
+                .class(&placement.class)
+                .ok_or_else(|| anyhow::anyhow!("the rules have no {}", placement.class))?;
+
+            let pos = Position::new(placement.x, placement.y);
+            state.add_ship(Ship::new(class.clone(), pos, placement.dir))?;
+        }
+
+        state.check()?;
+        Ok(state)
+    }
+}
+
+/// Read a text grid; each straight run of one symbol is a ship, split into
+/// ships of its class's length when several of the class lie end to end
+pub fn parse_grid(text: &str, rules: &GameRules) -> anyhow::Result<GameState> {
+    let rows: Vec<Vec<char>> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.chars().filter(|c| !c.is_whitespace()).collect())
+        .collect();
+
+    if rows.len() != rules.height as usize {
+        anyhow::bail!("grid has {} rows, the board is {} high", rows.len(), rules.height);
+    }
+    for (y, row) in rows.iter().enumerate() {
+        if row.len() != rules.width as usize {
+            anyhow::bail!("row {} has {} cells, the board is {} wide", y, row.len(), rules.width);
+        }
+    }
+
+    let symbol_at = |pos: Position| rows[pos.y as usize][pos.x as usize];
+    let class_of = |symbol: char| {
+        rules
+            .fleet
+            .iter()
+            .map(|entry| &entry.class)
+            .find(|class| class.symbol == symbol)
+    };
+
+    let mut state = GameState::new(rules.clone(), rand::random());
+    let mut seen = vec![false; (rules.width * rules.height) as usize];
+    for pos in rules.positions() {
+        let symbol = symbol_at(pos);
+        if WATER.contains(&symbol) || seen[(pos.y * rules.width + pos.x) as usize] {
+            continue;
+        }
+        let class = class_of(symbol)
+            .ok_or_else(|| anyhow::anyhow!("unknown symbol '{}' at {}", symbol, pos))?;
+
+        // Follow the run right if it makes whole ships there, otherwise down;
+        // a cell an earlier ship took ends the run, so ships may touch
+        let run = |dir| {
+            (0..)
+                .map(|offset| pos.step(dir, offset))
+                .take_while(|cell| {
+                    cell.in_bounds(rules)
+                        && symbol_at(*cell) == symbol
+                        && !seen[(cell.y * rules.width + cell.x) as usize]
+                })
+                .count() as u32
+        };
+        let (across, down) = (run(Direction::Horizontal), run(Direction::Vertical));
+        let (dir, run) = if across > 1 && across.is_multiple_of(class.span) {
+            (Direction::Horizontal, across)
+        } else if down.is_multiple_of(class.span) {
+            (Direction::Vertical, down)
+        } else {
+            anyhow::bail!(
+                "the {} at {} is {} cells long, it should be {}",
+                class.name,
+                pos,
+                if across > 1 { across } else { down },
+                class.span
+            );
+        };
+
+        for cell in (0..run).map(|offset| pos.step(dir, offset)) {
+            seen[(cell.y * rules.width + cell.x) as usize] = true;
+        }
+        for offset in (0..run).step_by(class.span as usize) {
+            state.add_ship(Ship::new(class.clone(), pos.step(dir, offset), dir))?;
+        }
+    }
+
+    state.check()?;
+    Ok(state)
+}
+
+/// The fleet as a text grid
+pub fn to_grid(state: &GameState) -> String {
+    let rules = &state.rules;
+    let mut grid = format!(
+        "# {}x{} board: one ship symbol per cell, '.' for water\n",
+        rules.width, rules.height
+    );
+    for y in 0..rules.height {
+        let row: Vec<String> = (0..rules.width)
+            .map(|x| {
+                let pos = Position::new(x, y);
+                state
+                    .ships
+                    .iter()
+                    .find(|ship| ship.points().any(|p| p == pos))
+                    .map_or('.', |ship| ship.class.symbol)
+                    .to_string()
+            })
+            .collect();
+        grid.push_str(&row.join(" "));
+        grid.push('\n');
+    }
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use battleship_core::{FleetEntry, GameMode, ShipClass};
+
+    const GRID: &str = "\
+# classic fleet
+A A A A A . . . . .
+. . . . . . . . . .
+B . . . . . . D D .
+B . . . . . . . . .
+B . . C C C . . . .
+B . . . . . . . . .
+. . . . . . . . . S
+. . . . . . . . . S
+. . . . . . . . . S
+. . . . . . . . . .
+";
+
+    #[test]
+    fn test_board_grid() {
+        let rules = GameRules::classic();
+        let state = parse_grid(GRID, &rules).unwrap();
+        assert_eq!(state.check(), Ok(()));
+        assert_eq!(state.ships.len(), 5);
+
+        // Exporting and loading again gives the same fleet under a new pepper
+        let reloaded = parse_grid(&to_grid(&state), &rules).unwrap();
+        assert_eq!(reloaded.ships, state.ships);
+        assert_ne!(reloaded.pepper, state.pepper);
+
+        let from_json = BoardFile::from_state(&state).to_state(&rules).unwrap();
+        assert_eq!(from_json.ships, state.ships);
+    }
+
+    #[test]
+    fn test_touching_ships() {
+        let rules = GameRules {
+            width: 4,
+            height: 3,
+            fleet: vec![FleetEntry { class: ShipClass::new("Destroyer", 'D', 2), count: 3 }],
+            mode: GameMode::Classic,
+        };
+        // A vertical destroyer at (0, 0) touches the horizontal one beside it
+        let grid = "D D D .\nD . . .\n. . D D\n";
+        let state = parse_grid(grid, &rules).unwrap();
+        let dirs: Vec<_> = state.ships.iter().map(|ship| (ship.pos, ship.dir)).collect();
+        assert_eq!(
+            dirs,
+            [
+                (Position::new(0, 0), Direction::Vertical),
+                (Position::new(1, 0), Direction::Horizontal),
+                (Position::new(2, 2), Direction::Horizontal),
+            ]
+        );
+        assert_eq!(parse_grid(&to_grid(&state), &rules).unwrap().ships, state.ships);
+    }
+
+    #[test]
+    fn test_board_errors() {
+        let rules = GameRules::classic();
+        let error = |grid: &str| parse_grid(grid, &rules).unwrap_err().to_string();
+
+        assert_eq!(
+            error(&GRID.replace("C C C", "C C .")),
+            "the Cruiser at (3, 4) is 2 cells long, it should be 3"
+        );
+        assert_eq!(
+            error(&GRID.replace("D D .", "D D X")),
+            "unknown symbol 'X' at (9, 2)"
+        );
+        assert_eq!(
+            error(&GRID.replace(". . . . . . . . . .\n", "")),
+            "grid has 8 rows, the board is 10 high"
+        );
+        assert_eq!(
+            error(&GRID.replace("C C C . . . .", "C C C . . .")),
+            "row 4 has 9 cells, the board is 10 wide"
+        );
+        assert_eq!(
+            error(&GRID.replace(". . . . . . . . . S", ". . . . . . . . . .")),
+            "the rules ask for 1 Submarine, the board has 0"
+        );
+
+        let overlap = BoardFile {
+            ships: vec![
+                ShipPlacement { class: "Carrier".into(), x: 0, y: 0, dir: Direction::Horizontal },
+                ShipPlacement { class: "Destroyer".into(), x: 2, y: 0, dir: Direction::Vertical },
+            ],
+        };
+        assert_eq!(
+            overlap.to_state(&rules).unwrap_err().to_string(),
+            "the Destroyer at (2, 0) overlaps the Carrier at (0, 0)"
+        );
+    }
+}
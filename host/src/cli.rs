@@ -0,0 +1,293 @@
+use battleship_core::{GameMode, GameRules, GameState};
+use battleship_host::ai::{
+    Difficulty, HuntTarget, Placement, ProbabilityDensity, RandomShots, ScriptedShots,
+};
+use battleship_host::game_coordinator::Player;
+use battleship_host::prover::{ProverConfig, DEFAULT_PROVER_CONNECTIONS, DEFAULT_PROVER_PORT};
+use battleship_host::tui::Tui;
+use battleship_host::verification::{ReceiptKind, VerificationPolicy};
+use battleship_host::{board_file, rules_setup, ship_placement};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Zero-knowledge battleship: every shot result is proven with RISC Zero.
+///
+/// Without a subcommand an interactive menu asks what to do.
+#[derive(Parser)]
+#[command(name = "battleship", version)]
+pub struct Cli {
+    /// Receipts to accept: strict, dev-fake or pinned:<file>. Picked from the
+    /// environment when left out
+    #[arg(long, global = true)]
+    pub policy: Option<VerificationPolicy>,
+
+    /// Where proofs are made: local, dev-fake or remote:<address> for a proving
+    /// daemon. Picked from the environment when left out
+    #[arg(long, global = true)]
+    pub prover: Option<ProverConfig>,
+
+    /// While the opponent aims, prove the results of the CACHE_SIZE shots they
+    /// are most likely to fire, so a good guess is answered at once. Classic mode only
+    #[arg(long, global = true, value_name = "CACHE_SIZE")]
+    pub speculate: Option<usize>,
+
+    /// Receipt kinds to accept and prove, favourite first: composite, succinct
+    /// or groth16. Whoever moves first gets their favourite of those both accept
+    #[arg(long, global = true, value_delimiter = ',', default_value = "composite,succinct")]
+    pub receipts: Vec<ReceiptKind>,
+
+    /// Play in a full-screen terminal UI with both boards side by side, placing
+    /// ships and aiming with the cursor, instead of plain text
+    #[arg(long, global = true)]
+    pub tui: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Host a game and wait for an opponent; the host moves first and picks the rules
+    Host {
+        #[command(flatten)]
+        listen: ListenArgs,
+        #[command(flatten)]
+        rules: RulesArgs,
+        #[command(flatten)]
+        game: GameArgs,
+    },
+    /// Join a game someone is hosting
+    Join {
+        /// Host's address, or unix:/path/to/socket
+        address: String,
+        #[arg(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+        #[command(flatten)]
+        game: GameArgs,
+    },
+    /// Play against the computer
+    Solo {
+        #[arg(long, value_enum, default_value_t = Difficulty::Medium)]
+        difficulty: Difficulty,
+        #[command(flatten)]
+        rules: RulesArgs,
+        #[command(flatten)]
+        game: GameArgs,
+    },
+    /// Resume a saved game; whoever moved first listens again, the other connects
+    Resume {
+        /// Saved game file, picked from a list when left out
+        save: Option<PathBuf>,
+        /// Address to listen on or connect to, or unix:/path/to/socket
+        #[arg(long)]
+        address: Option<String>,
+        #[arg(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+        #[command(flatten)]
+        shots: ShotArgs,
+    },
+    /// Check every proof in a game transcript and report the first invalid step
+    Verify {
+        transcript: PathBuf,
+    },
+    /// Step through a recorded game turn by turn
+    Replay {
+        transcript: PathBuf,
+    },
+    /// Fold a player's receipts from a game transcript into one whole-game
+    /// proof, written next to the transcript
+    ProveGame {
+        transcript: PathBuf,
+        /// 0 for the player who moved first, 1 for the other; both when left out
+        #[arg(long)]
+        player: Option<usize>,
+        /// Kind of the final receipt; succinct or groth16 keep it small
+        #[arg(long, default_value = "succinct")]
+        kind: ReceiptKind,
+    },
+    /// Check a whole-game proof and show what it attests to
+    VerifyGame {
+        proof: PathBuf,
+    },
+    /// Run a proving daemon that proves for any number of players.
+    ///
+    /// Players send it their secret boards unencrypted, so it only listens on
+    /// this machine unless told otherwise; reach it from others over an SSH tunnel.
+    Prover {
+        /// Address to listen on, or unix:/path/to/socket
+        #[arg(long, default_value_t = format!("127.0.0.1:{}", DEFAULT_PROVER_PORT))]
+        listen: String,
+        /// Requests to prove at once; later ones wait their turn
+        #[arg(long, default_value_t = DEFAULT_PROVER_CONNECTIONS)]
+        connections: usize,
+    },
+}
+
+#[derive(Args)]
+pub struct ListenArgs {
+    /// Address to listen on, or unix:/path/to/socket
+    #[arg(long, default_value = "0.0.0.0")]
+    pub bind: String,
+    #[arg(long, default_value_t = DEFAULT_PORT)]
+    pub port: u16,
+}
+
+/// The rules the host proposes. Left out parts are classic; with no rules
+/// flags at all they are asked for, unless the game is automated
+#[derive(Args, Default)]
+pub struct RulesArgs {
+    /// Board size WxH, e.g. 12x8
+    #[arg(long)]
+    size: Option<String>,
+    /// Fleet as name:symbol:length:count, comma separated
+    #[arg(long)]
+    fleet: Option<String>,
+    #[arg(long, value_enum)]
+    mode: Option<ModeArg>,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum ModeArg {
+    Classic,
+    Salvo,
+}
+
+impl RulesArgs {
+    pub fn resolve(&self, automated: bool) -> anyhow::Result<GameRules> {
+        if self.size.is_none() && self.fleet.is_none() && self.mode.is_none() && !automated {
+            return rules_setup::prompt_rules();
+        }
+
+        let mut rules = GameRules::classic();
+        if let Some(size) = &self.size {
+            (rules.width, rules.height) = rules_setup::parse_board_size(size)?;
+        }
+        if let Some(fleet) = &self.fleet {
+            rules.fleet = rules_setup::parse_fleet(fleet)?;
+        }
+        rules.mode = match self.mode {
+            Some(ModeArg::Salvo) => GameMode::Salvo,
+            Some(ModeArg::Classic) | None => GameMode::Classic,
+        };
+
+        if !rules.check() {
+            anyhow::bail!("that fleet does not fit those rules");
+        }
+        Ok(rules)
+    }
+}
+
+/// How this side is set up and played. Anything left out is asked for,
+/// unless the game is automated, where defaults are used instead
+#[derive(Args, Default)]
+pub struct GameArgs {
+    /// Your player name
+    #[arg(long)]
+    name: Option<String>,
+    /// Load your fleet from a board file: JSON if it ends in .json, otherwise
+    /// a text grid of ship symbols
+    #[arg(long, conflicts_with = "placement")]
+    board: Option<PathBuf>,
+    /// How to place your fleet
+    #[arg(long, value_enum)]
+    placement: Option<PlacementMode>,
+    #[command(flatten)]
+    pub shots: ShotArgs,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum PlacementMode {
+    Manual,
+    Random,
+    /// No two ships touch
+    Spread,
+    /// Ships hug the edges
+    Edges,
+}
+
+impl GameArgs {
+    pub fn name(&self) -> anyhow::Result<String> {
+        if let Some(name) = &self.name {
+            return Ok(name.clone());
+        }
+        if self.shots.automated() {
+            return Ok("Player".to_string());
+        }
+
+        print!("\nEnter your name: ");
+        io::stdout().flush()?;
+        let mut name = String::new();
+        io::stdin().read_line(&mut name)?;
+        Ok(name.trim().to_string())
+    }
+
+    /// Set up our fleet, with the cursor when the full-screen UI is up
+    pub fn place(&self, rules: &GameRules, tui: Option<&Tui>) -> anyhow::Result<GameState> {
+        if let Some(path) = &self.board {
+            let state = board_file::load(path, rules)?;
+            let loaded = format!("✅ Fleet loaded from {}", path.display());
+            match tui {
+                Some(tui) => tui.log(loaded),
+                None => println!("{}", loaded),
+            }
+            return Ok(state);
+        }
+
+        // Manual placement and the placement menu are what the cursor replaces
+        let placement = match self.placement {
+            Some(PlacementMode::Manual) => None,
+            Some(PlacementMode::Random) => Some(Placement::Uniform),
+            Some(PlacementMode::Spread) => Some(Placement::Spread),
+            Some(PlacementMode::Edges) => Some(Placement::Edges),
+            None if self.shots.automated() => Some(Placement::Uniform),
+            None => None,
+        };
+        match (placement, tui) {
+            (Some(placement), _) => placement.place(rules, &mut rand::thread_rng()),
+            (None, Some(tui)) => tui.place_fleet(rules),
+            (None, None) if self.placement.is_some() => ship_placement::manual_placement(rules),
+            (None, None) => ship_placement::interactive_ship_placement(rules),
+        }
+    }
+}
+
+/// Fire without prompting, for scripted and automated runs
+#[derive(Args, Default)]
+pub struct ShotArgs {
+    /// Fire the shots in this file, one x,y per line, then at random
+    #[arg(long, conflicts_with = "strategy")]
+    shots: Option<PathBuf>,
+    /// Let a strategy pick every shot
+    #[arg(long, value_enum)]
+    strategy: Option<StrategyArg>,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum StrategyArg {
+    Random,
+    /// Checkerboard hunting, then targeting around hits
+    Hunt,
+    /// Probability heat map of the remaining fleet
+    Heat,
+}
+
+impl ShotArgs {
+    pub fn automated(&self) -> bool {
+        self.shots.is_some() || self.strategy.is_some()
+    }
+
+    pub fn player(&self) -> anyhow::Result<Player> {
+        if let Some(path) = &self.shots {
+            return Ok(Player::Automated(Box::new(ScriptedShots::load(path)?)));
+        }
+        Ok(match self.strategy {
+            Some(StrategyArg::Random) => Player::Automated(Box::new(RandomShots::default())),
+            Some(StrategyArg::Hunt) => Player::Automated(Box::new(HuntTarget::default())),
+            Some(StrategyArg::Heat) => Player::Automated(Box::new(ProbabilityDensity::default())),
+            None => Player::Human,
+        })
+    }
+}
@@ -0,0 +1,160 @@
+use crate::network_protocol::GameMessage;
+use std::fmt::Display;
+use std::io::{ErrorKind, Read, Write};
+
+/// Largest frame accepted unless configured otherwise; comfortably fits a
+/// composite STARK receipt
+pub const DEFAULT_MAX_FRAME: usize = 32 * 1024 * 1024;
+
+/// How messages are encoded inside a frame
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Codec {
+    /// Compact binary encoding; receipts travel as raw bytes
+    #[default]
+    Bincode,
+    /// Human-readable JSON, for debugging the protocol
+    Json,
+}
+
+impl Codec {
+    pub fn encode(self, message: &GameMessage) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            Self::Bincode => bincode::serialize(message)?,
+            Self::Json => serde_json::to_vec(message)?,
+        })
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> anyhow::Result<GameMessage> {
+        Ok(match self {
+            Self::Bincode => bincode::deserialize(bytes)?,
+            Self::Json => serde_json::from_slice(bytes)?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// The peer closed the connection between frames
+    Closed,
+    /// A frame is longer than the configured maximum
+    TooLarge { size: usize, max: usize },
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Closed => write!(f, "Opponent closed the connection"),
+            Self::TooLarge { size, max } => {
+                write!(f, "Frame of {} bytes exceeds the {} byte limit", size, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+/// Write `payload` as one frame: a big-endian `u32` length, then the bytes
+pub fn write_frame(writer: &mut impl Write, payload: &[u8], max: usize) -> anyhow::Result<()> {
+    if payload.len() > max || u32::try_from(payload.len()).is_err() {
+        return Err(FrameError::TooLarge {
+            size: payload.len(),
+            max,
+        }
+        .into());
+    }
+
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read one frame, refusing lengths over `max` before allocating anything
+pub fn read_frame(reader: &mut impl Read, max: usize) -> anyhow::Result<Vec<u8>> {
+    let mut header = [0u8; 4];
+    let mut filled = 0;
+    while filled < header.len() {
+        match reader.read(&mut header[filled..]) {
+            Ok(0) if filled == 0 => return Err(FrameError::Closed.into()),
+            Ok(0) => anyhow::bail!("Connection closed in the middle of a frame header"),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let size = u32::from_be_bytes(header) as usize;
+    if size > max {
+        return Err(FrameError::TooLarge { size, max }.into());
+    }
+
+    let mut payload = vec![0u8; size];
+    reader.read_exact(&mut payload).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => anyhow::anyhow!("Connection closed in the middle of a frame"),
+        _ => e.into(),
+    })?;
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verification::{ImageIds, ReceiptKind, VerificationPolicy};
+    use battleship_core::GameRules;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_frames() {
+        let message = GameMessage::Hello {
+            nonce: [7; 16],
+            policy: VerificationPolicy::Pinned(vec![ImageIds::local()]),
+            image_ids: ImageIds::local(),
+            rules: GameRules::classic(),
+            receipt_kinds: vec![ReceiptKind::Succinct, ReceiptKind::Composite],
+        };
+
+        for codec in [Codec::Bincode, Codec::Json] {
+            let mut wire = Vec::new();
+            write_frame(&mut wire, &codec.encode(&message).unwrap(), 1024).unwrap();
+            write_frame(&mut wire, &codec.encode(&message).unwrap(), 1024).unwrap();
+
+            let mut reader = Cursor::new(wire);
+            for _ in 0..2 {
+                let frame = read_frame(&mut reader, 1024).unwrap();
+                let decoded = codec.decode(&frame).unwrap();
+                assert_eq!(format!("{:?}", decoded), format!("{:?}", message));
+            }
+
+            let closed = read_frame(&mut reader, 1024).unwrap_err();
+            assert_eq!(closed.downcast_ref(), Some(&FrameError::Closed));
+        }
+    }
+
+    #[test]
+    fn test_frame_limits() {
+        let too_large = write_frame(&mut Vec::new(), &[0; 17], 16).unwrap_err();
+        assert_eq!(
+            too_large.downcast_ref(),
+            Some(&FrameError::TooLarge { size: 17, max: 16 })
+        );
+
+        // The length is checked before the payload is read or allocated
+        let mut reader = Cursor::new(u32::MAX.to_be_bytes().to_vec());
+        let too_large = read_frame(&mut reader, 16).unwrap_err();
+        assert_eq!(
+            too_large.downcast_ref(),
+            Some(&FrameError::TooLarge {
+                size: u32::MAX as usize,
+                max: 16
+            })
+        );
+
+        let mut wire = Vec::new();
+        write_frame(&mut wire, &[1, 2, 3, 4], 16).unwrap();
+        wire.truncate(6);
+        let truncated = read_frame(&mut Cursor::new(wire), 16).unwrap_err();
+        assert!(truncated.downcast_ref::<FrameError>().is_none());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::board_display::BoardDisplay;
+use battleship_core::{GameMode, GameState, HitType, Position};
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// What a frontend shows of the game between moves
+pub struct View<'a> {
+    pub player_name: &'a str,
+    pub opponent_name: &'a str,
+    pub trust: String,
+    pub my_turn: bool,
+    pub mine: &'a BoardDisplay,
+    pub opponent: &'a BoardDisplay,
+    pub fleet: &'a GameState,
+}
+
+/// Where the game is shown and a person's shots come from; the coordinator
+/// drives it while proving, verifying and talking to the opponent itself
+pub trait Frontend {
+    /// Report progress or a result
+    fn say(&self, message: &str);
+
+    /// The boxed heading of a turn, for frontends without one of their own
+    fn banner(&self, _title: &str) {}
+
+    /// Both boards at the start of a turn
+    fn show_turn(&self, view: &View);
+
+    /// The proven results of one turn's shots; `ours` when we fired them
+    fn show_result(&self, view: &View, ours: bool, shots: &[Position], hits: &[HitType]);
+
+    /// Ask for the next shot, avoiding the `pending` shots of the salvo
+    fn choose_shot(&self, view: &View, pending: &[Position]) -> anyhow::Result<Position>;
+
+    /// What is being waited for or proven right now
+    fn status(&self, _status: &str) {}
+
+    /// A proof finished after `elapsed`
+    fn proved(&self, _what: &str, _elapsed: Duration) {}
+
+    /// Give the person time to read a result
+    fn pause(&self) {}
+
+    /// The result is signed and saved; the last chance to look at the boards
+    fn game_over(&self, _won: bool) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// One word or two for a proven shot result
+pub fn describe(hit: &HitType) -> String {
+    match hit {
+        HitType::Miss => "miss".to_string(),
+        HitType::Hit => "HIT".to_string(),
+        HitType::Sunk(ship) => format!("SUNK {}", ship),
+        HitType::Repeat => "already fired there".to_string(),
+    }
+}
+
+/// Printed to the terminal, prompting on stdin
+pub struct PlainText;
+
+/// Shows nothing, for the computer's side of a game
+pub struct Silent;
+
+impl Frontend for PlainText {
+    fn say(&self, message: &str) {
+        println!("{}", message);
+    }
+
+    fn banner(&self, title: &str) {
+        println!("\n╔═══════════════════════════════════════╗");
+        println!("║        {:<31}║", title);
+        println!("╚═══════════════════════════════════════╝");
+    }
+
+    fn show_turn(&self, view: &View) {
+        print!("\x1B[2J\x1B[1;1H");
+
+        println!("\n");
+        println!("╔═══════════════════════════════════════════════╗");
+        println!("║  {} vs {}                    ", view.player_name, view.opponent_name);
+        println!("║  Trust: {}", view.trust);
+        println!("║  Your Ships: {} | Opponent Ships: {}            ",
+                 view.mine.ships_remaining(),
+                 view.opponent.ships_remaining());
+        if view.my_turn {
+            println!("║  >>> YOUR TURN <<<                            ║");
+        } else {
+            println!("║  >>> OPPONENT'S TURN <<<                      ║");
+        }
+        println!("╚═══════════════════════════════════════════════╝");
+
+        view.opponent.display_opponent_board();
+        view.mine.display_own_board(view.fleet);
+    }
+
+    fn show_result(&self, view: &View, ours: bool, shots: &[Position], hits: &[HitType]) {
+        print!("\x1B[2J\x1B[1;1H");
+        println!("\n");
+
+        if view.fleet.rules.mode == GameMode::Salvo {
+            let title = if ours { "SALVO RESULT" } else { "OPPONENT'S SALVO RESULT" };
+            println!("╔═══════════════════════════════════════════════╗");
+            println!("║  {:<45}║", title);
+            for (shot, hit) in shots.iter().zip(hits) {
+                println!("║  {:<10} {:<34}║", shot.to_string(), describe(hit));
+            }
+            println!("╚═══════════════════════════════════════════════╝");
+
+            println!("\nOPPONENT'S BOARD:");
+            view.opponent.display_opponent_board();
+            if !ours {
+                view.mine.display_own_board(view.fleet);
+            }
+            return;
+        }
+
+        println!("╔═══════════════════════════════════════════════╗");
+        if ours {
+            println!("║  SHOT RESULT                                  ║");
+            match &hits[0] {
+                HitType::Miss => println!("║  MISS!                                        ║"),
+                HitType::Hit => println!("║  HIT!                                         ║"),
+                HitType::Sunk(ship) => println!("║  SUNK {}!                              ║", ship),
+                HitType::Repeat => println!("║  ALREADY FIRED THERE!                         ║"),
+            }
+        } else {
+            println!("║  OPPONENT'S SHOT RESULT                       ║");
+            match &hits[0] {
+                HitType::Miss => println!("║  They MISSED!                                 ║"),
+                HitType::Hit => println!("║  They HIT your ship!                          ║"),
+                HitType::Sunk(ship) => println!("║  They SUNK your {}!                    ║", ship),
+                HitType::Repeat => println!("║  They fired at the same cell again!           ║"),
+            }
+        }
+        println!("╚═══════════════════════════════════════════════╝");
+
+        // After our own shot only the opponent's board changed
+        if ours {
+            println!("\nOPPONENT'S BOARD (Updated):");
+            view.opponent.display_opponent_board();
+        } else {
+            println!("\nOPPONENT'S BOARD:");
+            view.opponent.display_opponent_board();
+
+            println!("\nYOUR BOARD (Updated with damage):");
+            view.mine.display_own_board(view.fleet);
+        }
+    }
+
+    fn choose_shot(&self, view: &View, _pending: &[Position]) -> anyhow::Result<Position> {
+        let rules = view.opponent.rules();
+        loop {
+            print!("Enter coordinates to fire (x,y): ");
+            io::stdout().flush()?;
+
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
+
+            let parts: Vec<&str> = input.trim().split(',').collect();
+            if parts.len() != 2 {
+                println!("Invalid format. Use: x,y");
+                continue;
+            }
+
+            let x: u32 = match parts[0].trim().parse() {
+                Ok(v) if v < rules.width => v,
+                _ => {
+                    println!("X must be 0-{}", rules.width - 1);
+                    continue;
+                }
+            };
+
+            let y: u32 = match parts[1].trim().parse() {
+                Ok(v) if v < rules.height => v,
+                _ => {
+                    println!("Y must be 0-{}", rules.height - 1);
+                    continue;
+                }
+            };
+
+            let shot = Position::new(x, y);
+            if view.opponent.already_shot(shot) {
+                println!("You already fired at {}", shot);
+                continue;
+            }
+
+            return Ok(shot);
+        }
+    }
+
+    fn pause(&self) {
+        std::thread::sleep(Duration::from_secs(2));
+    }
+}
+
+impl Frontend for Silent {
+    fn say(&self, _message: &str) {}
+
+    fn show_turn(&self, _view: &View) {}
+
+    fn show_result(&self, _view: &View, _ours: bool, _shots: &[Position], _hits: &[HitType]) {}
+
+    fn choose_shot(&self, _view: &View, _pending: &[Position]) -> anyhow::Result<Position> {
+        anyhow::bail!("nobody is watching this side to choose a shot")
+    }
+}
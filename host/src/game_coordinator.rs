@@ -1,358 +1,1304 @@
+use crate::ai::{self, ShotStrategy};
 use crate::board_display::BoardDisplay;
-use crate::network::NetworkConnection;
-use crate::network_protocol::{GameMessage, ProofData};
-use battleship_core::{GameState, HitType, Position, RoundCommit, RoundInput};
-use battleship_guests::{ROUND_ELF, ROUND_ID};
-use risc0_zkvm::{default_prover, sha::Digest, ExecutorEnv};
-use std::io::{self, Write};
-
-pub struct GameCoordinator {
-    my_state: GameState,
-    my_commitment: Digest,
+use crate::frontend::{Frontend, PlainText, Silent, View};
+use crate::keys::{fingerprint, key_dir};
+use crate::network::{SignedTransport, Transport};
+use crate::network_protocol::{
+    BoardProof, ChainHead, GameMessage, GameResult, ProofData, SalvoProofData, SignedGameResult,
+};
+use crate::prover::{LocalProver, ProofRequest, ShotProver};
+use crate::saved_game::SavedGame;
+use crate::speculation::SpeculativeProver;
+use crate::transcript::{self, TranscriptHeader, TranscriptWriter};
+use crate::verification::{ImageIds, ReceiptKind, VerificationPolicy};
+use battleship_core::game::{Action, Event, Match, MatchError, Phase, Proven};
+use battleship_core::{
+    session_id, BoardCommit, BoardInput, GameMode, GameRules, GameState, HitType, Position,
+    RoundCommit, RoundInput, SalvoCommit, SalvoInput,
+};
+use risc0_zkvm::{sha::Digest, Receipt};
+use std::sync::Arc;
+
+/// `println!` through the frontend, which stays quiet when the computer plays
+/// this side and goes to the log when the full-screen UI is up
+macro_rules! say {
+    ($self:ident, $($arg:tt)*) => {
+        $self.frontend.say(&format!($($arg)*))
+    };
+}
+
+/// Who picks this side's shots
+pub enum Player {
+    Human,
+    /// A person's side played without prompts, by a script or strategy
+    Automated(Box<dyn ShotStrategy + Send>),
+    Computer(Box<dyn ShotStrategy + Send>),
+}
+
+/// What both players agreed on in the `Hello` exchange
+pub struct Session {
+    pub id: Digest,
+    pub rules: GameRules,
+    pub peer_ids: ImageIds,
+    /// The receipt kind both players prove with
+    pub receipt_kind: ReceiptKind,
+    /// The kinds we accept, which the opponent's receipts are checked against
+    pub accepted_kinds: Vec<ReceiptKind>,
+}
+
+/// Drives a `Match` over the network: proves and verifies what it asks for,
+/// asks the player for shots through the frontend and saves after every turn
+pub struct GameCoordinator<T: Transport> {
+    game: Match,
     my_display: BoardDisplay,
-    
-    opponent_commitment: Digest,
     opponent_display: BoardDisplay,
-    
-    network: NetworkConnection,
+    last_result: Option<GameMessage>,
+
+    network: SignedTransport<T>,
+    policy: VerificationPolicy,
+    peer_ids: ImageIds,
+    receipt_kind: ReceiptKind,
+    accepted_kinds: Vec<ReceiptKind>,
+    player: Player,
     player_name: String,
     opponent_name: String,
-    is_my_turn: bool,
+    /// Save after every turn so the game can be resumed
+    saves: bool,
+    transcript: Option<TranscriptWriter>,
+    frontend: Box<dyn Frontend>,
+    prover: Arc<dyn ShotProver>,
+    /// Set when results are proven ahead for the shots the opponent is likely to fire
+    speculation: Option<Arc<SpeculativeProver>>,
 }
 
-impl GameCoordinator {
+impl<T: Transport> GameCoordinator<T> {
+    /// Agree on the session, verification policy, rules and receipt kind before
+    /// ships are placed.
+    ///
+    /// The player who moves first sets the rules and gets their favourite of
+    /// the receipt kinds both accept. Nothing is printed, since the computer
+    /// opponent negotiates too.
+    pub fn negotiate(
+        network: &mut SignedTransport<T>,
+        starts_first: bool,
+        policy: &VerificationPolicy,
+        rules: GameRules,
+        receipt_kinds: &[ReceiptKind],
+    ) -> anyhow::Result<Session> {
+        let my_nonce: [u8; 16] = rand::random();
+        network.send(&GameMessage::Hello {
+            nonce: my_nonce,
+            policy: policy.clone(),
+            image_ids: ImageIds::local(),
+            rules: rules.clone(),
+            receipt_kinds: receipt_kinds.to_vec(),
+        })?;
+
+        let (their_nonce, peer_ids, their_rules, their_kinds) = match network.receive()? {
+            GameMessage::Hello { nonce, policy: peer_policy, image_ids, rules, receipt_kinds } => {
+                match policy.accept_peer(&peer_policy, &image_ids) {
+                    Ok(peer_ids) => (nonce, peer_ids, rules, receipt_kinds),
+                    Err(e) => {
+                        network.send(&GameMessage::Error {
+                            message: format!("Refusing to play: {}", e),
+                        })?;
+                        anyhow::bail!("Refusing to play: {}", e);
+                    }
+                }
+            }
+            GameMessage::Error { message } => {
+                anyhow::bail!("Opponent aborted the handshake: {}", message)
+            }
+            _ => anyhow::bail!("Expected Hello message"),
+        };
+
+        // The first player's nonce goes first so both sides derive the same id
+        let (id, rules) = if starts_first {
+            (session_id(&my_nonce, &their_nonce), rules)
+        } else {
+            (session_id(&their_nonce, &my_nonce), their_rules)
+        };
+        network.bind_session(&id);
+        if !rules.check() {
+            anyhow::bail!("The host proposed unplayable rules");
+        }
+
+        let receipt_kind = if starts_first {
+            ReceiptKind::negotiate(receipt_kinds, &their_kinds)
+        } else {
+            ReceiptKind::negotiate(&their_kinds, receipt_kinds)
+        };
+        let Some(receipt_kind) = receipt_kind else {
+            // Both sides see the mismatch, so neither waits on the other
+            anyhow::bail!(
+                "No receipt kind both players accept: ours are {}, the opponent's are {}",
+                ReceiptKind::format_list(receipt_kinds),
+                ReceiptKind::format_list(&their_kinds)
+            );
+        };
+
+        Ok(Session {
+            id,
+            rules,
+            peer_ids,
+            receipt_kind,
+            accepted_kinds: receipt_kinds.to_vec(),
+        })
+    }
+
     pub fn new(
         my_state: GameState,
-        my_commitment: Digest,
-        network: NetworkConnection,
+        network: SignedTransport<T>,
+        player: Player,
         player_name: String,
         starts_first: bool,
-    ) -> Self {
-        Self {
-            my_state,
-            my_commitment,
-            my_display: BoardDisplay::new(),
-            opponent_commitment: Digest::default(),
-            opponent_display: BoardDisplay::new(),
+        policy: VerificationPolicy,
+        session: Session,
+    ) -> anyhow::Result<Self> {
+        let mut game = Match::new(session.id, session.rules.clone(), starts_first);
+        game.handle(Event::Placed(my_state))?;
+
+        let saves = !matches!(player, Player::Computer(_));
+        let frontend: Box<dyn Frontend> = match player {
+            Player::Computer(_) => Box::new(Silent),
+            _ => Box::new(PlainText),
+        };
+        Ok(Self {
+            game,
+            my_display: BoardDisplay::new(session.rules.clone()),
+            opponent_display: BoardDisplay::new(session.rules),
+            last_result: None,
             network,
+            policy,
+            peer_ids: session.peer_ids,
+            receipt_kind: session.receipt_kind,
+            accepted_kinds: session.accepted_kinds,
+            player,
             player_name,
             opponent_name: String::new(),
-            is_my_turn: starts_first,
+            saves,
+            transcript: None,
+            frontend,
+            prover: Arc::new(LocalProver),
+            speculation: None,
+        })
+    }
+
+    /// Keep nothing on disk, for games that cannot be resumed such as against the computer
+    pub fn without_saves(mut self) -> Self {
+        self.saves = false;
+        self
+    }
+
+    /// Show the game somewhere other than printed to the terminal, such as the full-screen UI
+    pub fn with_frontend(mut self, frontend: impl Frontend + 'static) -> Self {
+        self.frontend = Box::new(frontend);
+        self
+    }
+
+    /// Prove with another backend, such as a proving daemon shared on the LAN
+    pub fn with_prover(mut self, prover: Box<dyn ShotProver>) -> Self {
+        self.prover = Arc::from(prover);
+        self
+    }
+
+    /// While waiting for the opponent's shot, prove our results for the
+    /// `cache_size` cells they are most likely to pick. Classic mode only, as
+    /// a salvo has too many combinations to guess. Set after `with_prover`.
+    pub fn with_speculation(mut self, cache_size: usize) -> Self {
+        let speculation = Arc::new(SpeculativeProver::new(self.prover.clone(), cache_size));
+        self.prover = speculation.clone();
+        self.speculation = Some(speculation);
+        self
+    }
+
+    /// Reconnect to a saved game, continuing from the last turn both players
+    /// verified and resending our last result if the opponent never got it
+    pub fn resume(
+        saved: SavedGame,
+        network: SignedTransport<T>,
+        player: Player,
+    ) -> anyhow::Result<Self> {
+        let mut coordinator = Self {
+            game: saved.game,
+            my_display: saved.my_display,
+            opponent_display: saved.opponent_display,
+            last_result: saved.last_result,
+            network,
+            policy: saved.policy,
+            peer_ids: saved.peer_ids,
+            receipt_kind: saved.receipt_kind,
+            accepted_kinds: saved.accepted_kinds,
+            player,
+            player_name: saved.player_name,
+            opponent_name: saved.opponent_name,
+            saves: true,
+            transcript: None,
+            frontend: Box::new(PlainText),
+            prover: Arc::new(LocalProver),
+            speculation: None,
+        };
+        let session = coordinator.game.session();
+        coordinator.transcript = Some(TranscriptWriter::reopen(
+            &transcript::path(&session),
+            saved.transcript_len,
+        )?);
+
+        say!(coordinator, "\n🔄 Resuming game against {}...", coordinator.opponent_name);
+        let verified = coordinator.game.turns_verified();
+        let proven = coordinator.game.turns_proven();
+        coordinator.network.send(&GameMessage::Resume {
+            session,
+            verified,
+            proven,
+            sent: ChainHead::of(&saved.sent_chain),
+            received: ChainHead::of(&saved.received_chain),
+        })?;
+
+        let (their_verified, their_proven, their_sent, their_received) =
+            match coordinator.network.receive()? {
+                GameMessage::Resume { session: theirs, verified, proven, sent, received } => {
+                    if theirs != session {
+                        coordinator.network.send(&GameMessage::Error {
+                            message: "Resuming a different game".to_string(),
+                        })?;
+                        anyhow::bail!("Opponent is resuming a different game");
+                    }
+                    (verified, proven, sent, received)
+                }
+                GameMessage::Error { message } => {
+                    anyhow::bail!("Opponent could not resume: {}", message)
+                }
+                _ => anyhow::bail!("Expected Resume message"),
+            };
+
+        let agreed = (|| {
+            if coordinator.network.peer_identity() != Some(saved.peer_identity) {
+                anyhow::bail!("a different player is resuming this game");
+            }
+            // Either side may have proven one more turn than the other verified
+            let awaiting = match their_proven.checked_sub(verified) {
+                Some(0) => false,
+                Some(1) => true,
+                _ => anyhow::bail!("{} turns were proven but {} verified", their_proven, verified),
+            };
+            let resend = match proven.checked_sub(their_verified) {
+                Some(0) => false,
+                Some(1) => true,
+                _ => anyhow::bail!("{} turns were proven but {} verified", proven, their_verified),
+            };
+            let sent = agree_chain(saved.sent_chain, their_received)?;
+            let received = agree_chain(saved.received_chain, their_sent)?;
+            Ok((awaiting, resend, sent, received))
+        })();
+
+        let (awaiting, resend, sent, received) = match agreed {
+            Ok(agreed) => agreed,
+            Err(e) => {
+                coordinator.network.send(&GameMessage::Error {
+                    message: format!("Cannot resume: {}", e),
+                })?;
+                anyhow::bail!("Cannot resume: {}", e);
+            }
+        };
+        coordinator.network.restore_chains(sent, received);
+
+        if awaiting {
+            // The shots were saved before they were sent, so they are not in the transcript yet
+            let shots = coordinator.shots_message(coordinator.game.pending_shots().to_vec());
+            coordinator.record(true, &shots)?;
+        } else {
+            coordinator.game.retract_shots();
+        }
+        if resend {
+            let result = coordinator
+                .last_result
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("No saved result to resend"))?;
+            say!(coordinator, "↩️  Resending our last proven result...");
+            coordinator.network.send(&result)?;
         }
+
+        say!(coordinator, "✓ Game resumed");
+        Ok(coordinator)
     }
 
     pub fn handshake(&mut self) -> anyhow::Result<()> {
-        println!("\n🤝 Exchanging board commitments...");
-        
-        self.network.send(&GameMessage::BoardReady {
-            commitment: self.my_commitment,
-            player_name: self.player_name.clone(),
-        })?;
-        
-        match self.network.receive()? {
-            GameMessage::BoardReady { commitment, player_name } => {
-                self.opponent_commitment = commitment;
-                self.opponent_name = player_name.clone();
-                println!("✓ Received commitment from {}", player_name);
-                println!("   Opponent Commitment: {:?}", commitment);
+        if let Some(fingerprint) = self.network.peer_fingerprint() {
+            say!(self, "\n🔑 Encrypted channel, opponent key fingerprint: {}", fingerprint);
+            say!(self, "   Confirm it with your opponent out of band before trusting the game");
+        }
+
+        if self.is_human() {
+            let session = self.game.session();
+            let (mine, theirs) = (ImageIds::local(), self.peer_ids);
+            let header = TranscriptHeader {
+                session,
+                rules: self.game.rules().clone(),
+                image_ids: if self.game.starts_first() { [mine, theirs] } else { [theirs, mine] },
+            };
+            self.transcript = Some(TranscriptWriter::create(&transcript::path(&session), header)?);
+        }
+
+        // Prove and send our board, then verify theirs
+        for action in self.game.next_actions() {
+            if let Some(event) = self.perform(action)? {
+                self.handle(event)?;
             }
-            _ => anyhow::bail!("Expected BoardReady message"),
         }
-        
-        println!("\n✓ Handshake complete! Game starting...\n");
+
+        say!(self, "✅ Opponent's board is proven valid!");
+        say!(self, "   Opponent Commitment: {:?}", self.game.opponent_initial_commitment());
+        say!(self, "\n✓ Handshake complete! Game starting...\n");
         Ok(())
     }
 
+    /// Play until a fleet is sunk, carrying out what the match asks for and
+    /// feeding it what comes back; a resumed game picks up where it was saved
     pub fn play_game(&mut self) -> anyhow::Result<()> {
-        loop {
-            if self.is_my_turn {
-                
-                // ✅ Show both boards ONLY at start of your turn
-                self.display_boards();
-
-                loop {
-                    let hit_result = self.take_turn()?;
+        self.save()?;
 
-                    // ✅ After each shot, show ONLY opponent board
-                    self.display_opponent_board_after_shot(&hit_result);
+        let mut actions = self.game.next_actions();
+        loop {
+            // Only the last action of a step waits for something to happen
+            let mut event = None;
+            for action in actions {
+                event = self.perform(action)?;
+            }
+            match event {
+                Some(event) => actions = self.handle(event)?,
+                None => break,
+            }
+        }
 
-                    if self.opponent_display.ships_remaining() == 0 {
-                        println!("\n*** YOU WIN! All opponent ships destroyed! ***");
-                        self.network.send(&GameMessage::GameOver {
-                            winner: self.player_name.clone(),
-                        })?;
-                        return Ok(());
-                    }
+        match *self.game.phase() {
+            Phase::Finished { won: true } => {
+                say!(self, "\n*** YOU WIN! All opponent ships destroyed and proven sunk! ***");
+                self.finish_game(true)
+            }
+            Phase::Finished { won: false } => {
+                say!(self, "\n*** YOU LOSE! All your ships destroyed! ***");
+                self.finish_game(false)
+            }
+            ref phase => anyhow::bail!("The game stopped {}", phase),
+        }
+    }
 
-                    match hit_result {
-                        HitType::Miss => {
-                            println!("\nYou missed! Turn passes to opponent.\n");
-                            std::thread::sleep(std::time::Duration::from_secs(2));
-                            break;
-                        }
-                        HitType::Hit => {
-                            println!("\nHIT! You get another shot!\n");
-                            std::thread::sleep(std::time::Duration::from_secs(2));
-                            // ✅ Keep looping WITHOUT refreshing both boards
-                        }
-                        HitType::Sunk(_) => {
-                            println!("\nSHIP SUNK! You get another shot!\n");
-                            std::thread::sleep(std::time::Duration::from_secs(2));
-                        }
-                    }
+    /// Carry out one action; those that wait on the player or the opponent
+    /// return the event it led to
+    fn perform(&mut self, action: Action) -> anyhow::Result<Option<Event>> {
+        match action {
+            Action::ProveBoard(input) => {
+                self.prove_board(&input)?;
+                Ok(None)
+            }
+            Action::AwaitBoard => self.receive_board().map(Some),
+            Action::ChooseShots(count) => self.choose_shots(count).map(Some),
+            Action::SendShots(shots) => {
+                // Saved before sending, so the shots are awaited again on resume
+                self.save()?;
+                match shots[..] {
+                    [shot] => say!(self, "\nFiring at {}...", shot),
+                    _ => say!(self, "\nFiring salvo..."),
                 }
-            } else {
-                // ✅ Opponent turn still shows both boards normally
-                loop {
-                    self.display_boards();
-                    
-                    let hit_result = self.respond_to_shot()?;
-
-                    self.display_boards_after_opponent_shot(&hit_result);
-
-                    if self.my_display.ships_remaining() == 0 {
-                        println!("\n*** YOU LOSE! All your ships destroyed! ***");
-                        return Ok(());
-                    }
+                let message = self.shots_message(shots);
+                self.send(&message)?;
+                Ok(None)
+            }
+            Action::AwaitResult => self.receive_result().map(Some),
+            Action::ProveRound { input, commit } => {
+                self.prove_round(&input, commit)?;
+                Ok(None)
+            }
+            Action::ProveSalvo { input, commit } => {
+                self.prove_salvo(&input, commit)?;
+                Ok(None)
+            }
+            Action::AwaitShots => {
+                self.speculate();
+                self.receive_shots().map(Some)
+            }
+            Action::AwaitConcession => self.await_concession().map(Some),
+            Action::Concede(input) => {
+                self.concede(&input)?;
+                Ok(None)
+            }
+        }
+    }
 
-                    match hit_result {
-                        HitType::Miss => {
-                            println!("\nOpponent missed! Your turn!\n");
-                            std::thread::sleep(std::time::Duration::from_secs(2));
-                            break;
-                        }
-                        HitType::Hit => {
-                            println!("\nOpponent hit! They shoot again...\n");
-                            std::thread::sleep(std::time::Duration::from_secs(2));
-                        }
-                        HitType::Sunk(_) => {
-                            println!("\nOpponent sunk a ship! They shoot again...\n");
-                            std::thread::sleep(std::time::Duration::from_secs(2));
-                        }
-                    }
-                }
+    /// Hand an event to the match, telling the opponent when it is their
+    /// fault that it was refused, and show the result of our shots
+    fn handle(&mut self, event: Event) -> anyhow::Result<Vec<Action>> {
+        let phase = self.game.phase().clone();
+        let fired = match &event {
+            Event::Result { shots, hits, .. } => Some((shots.clone(), hits.clone())),
+            _ => None,
+        };
+        let conceded = matches!(event, Event::Conceded(_));
+
+        let actions = match self.game.handle(event) {
+            Ok(actions) => actions,
+            Err(MatchError::IllegalShots(message)) => {
+                self.send(&GameMessage::Error {
+                    message: message.clone(),
+                })?;
+                anyhow::bail!("Opponent fired illegally: {}", message);
+            }
+            Err(MatchError::BadProof(message)) if phase == Phase::Handshake => {
+                self.send(&GameMessage::Error {
+                    message: format!("Board proof rejected: {}", message),
+                })?;
+                anyhow::bail!("Opponent's board was rejected: {}", message);
+            }
+            Err(MatchError::BadProof(message)) => {
+                anyhow::bail!("⚠️  CHEATING DETECTED: {}", message)
             }
-            
-            self.is_my_turn = !self.is_my_turn;
+            Err(e) => return Err(e.into()),
+        };
+
+        if let Some((shots, hits)) = fired {
+            self.show_our_result(&shots, &hits);
+        }
+        if conceded {
+            say!(self, "✅ Game over proof verified!");
         }
+        Ok(actions)
     }
 
-    fn display_boards(&self) {
-        print!("\x1B[2J\x1B[1;1H");
-        
-        println!("\n");
-        println!("╔═══════════════════════════════════════════════╗");
-        println!("║  {} vs {}                    ", self.player_name, self.opponent_name);
-        println!("║  Your Ships: {} | Opponent Ships: {}            ", 
-                 self.my_display.ships_remaining(),
-                 self.opponent_display.ships_remaining());
-        if self.is_my_turn {
-            println!("║  >>> YOUR TURN <<<                            ║");
+    /// Audit the opponent, then sign the result together and keep a copy
+    fn finish_game(&mut self, won: bool) -> anyhow::Result<()> {
+        self.reveal_and_audit()?;
+        let result = self.sign_result(won)?;
+
+        let session = self.game.session();
+        if self.saves {
+            let _ = std::fs::remove_file(SavedGame::path(&session));
+        }
+        if let Some(transcript) = &mut self.transcript {
+            transcript.finish(&result)?;
+            let path = transcript::path(&session);
+            say!(self, "📜 Transcript saved to {}", path.display());
+            say!(self, "   `battleship prove-game {}` makes whole-game proofs", path.display());
+        }
+        if self.is_human() {
+            let dir = key_dir().join("results");
+            std::fs::create_dir_all(&dir)?;
+            let path = dir.join(format!("{}.json", session));
+            std::fs::write(&path, serde_json::to_string_pretty(&result)?)?;
+            say!(self, "📝 Result signed by both players, saved to {}", path.display());
+        }
+        if let Some(speculation) = &self.speculation {
+            say!(self, "⚡ Speculative proving: {}", speculation.stats());
+        }
+        if matches!(self.player, Player::Human) {
+            self.frontend.game_over(won)?;
+        }
+        Ok(())
+    }
+
+    /// Build the `GameResult`, sign it and check the opponent signed the same one
+    fn sign_result(&mut self, won: bool) -> anyhow::Result<SignedGameResult> {
+        let me = self.network.identity().public();
+        let opponent = self
+            .network
+            .peer_identity()
+            .ok_or_else(|| anyhow::anyhow!("Opponent never identified themselves"))?;
+
+        let starts_first = self.game.starts_first();
+        let my_commitment = self.game.my_initial_state().commit();
+        let their_commitment = self.game.opponent_initial_commitment();
+        let mine = (me, self.player_name.clone(), my_commitment);
+        let theirs = (opponent, self.opponent_name.clone(), their_commitment);
+        let (first, second) = if starts_first {
+            (mine, theirs)
         } else {
-            println!("║  >>> OPPONENT'S TURN <<<                      ║");
-        }
-        println!("╚═══════════════════════════════════════════════╝");
-        
-        self.opponent_display.display_opponent_board();
-        self.my_display.display_own_board(&self.my_state);
-    }
-
-    fn display_opponent_board_after_shot(&self, hit_type: &HitType) {
-        print!("\x1B[2J\x1B[1;1H");
-        
-        println!("\n");
-        println!("╔═══════════════════════════════════════════════╗");
-        println!("║  SHOT RESULT                                  ║");
-        match hit_type {
-            HitType::Miss => println!("║  MISS!                                        ║"),
-            HitType::Hit => println!("║  HIT!                                         ║"),
-            HitType::Sunk(ship) => println!("║  SUNK {:?}!                              ║", ship),
-        }
-        println!("╚═══════════════════════════════════════════════╝");
-        
-        println!("\nOPPONENT'S BOARD (Updated):");
-        self.opponent_display.display_opponent_board();
-
-        // ✅ NO self-board here — this was the bug
-    }
-
-    fn display_boards_after_opponent_shot(&self, hit_type: &HitType) {
-        print!("\x1B[2J\x1B[1;1H");
-        
-        println!("\n");
-        println!("╔═══════════════════════════════════════════════╗");
-        println!("║  OPPONENT'S SHOT RESULT                       ║");
-        match hit_type {
-            HitType::Miss => println!("║  They MISSED!                                 ║"),
-            HitType::Hit => println!("║  They HIT your ship!                          ║"),
-            HitType::Sunk(ship) => println!("║  They SUNK your {:?}!                    ║", ship),
-        }
-        println!("╚═══════════════════════════════════════════════╝");
-        
-        println!("\nOPPONENT'S BOARD:");
-        self.opponent_display.display_opponent_board();
-        
-        println!("\nYOUR BOARD (Updated with damage):");
-        self.my_display.display_own_board(&self.my_state);
-    }
-
-    fn take_turn(&mut self) -> anyhow::Result<HitType> {
-        println!("\n╔═══════════════════════════════════════╗");
-        println!("║        TAKE YOUR SHOT                 ║");
-        println!("╚═══════════════════════════════════════╝");
-        
-        let shot = self.prompt_shot()?;
-        
-        println!("\nFiring at {}...", shot);
-        self.network.send(&GameMessage::TakeShot { position: shot })?;
-        
-        println!("⏳ Waiting for ZK proof from opponent...");
-        match self.network.receive()? {
+            (theirs, mine)
+        };
+
+        let result = GameResult {
+            session: self.game.session(),
+            players: [first.0, second.0],
+            names: [first.1, second.1],
+            commitments: [first.2, second.2],
+            winner: if won { me } else { opponent },
+            transcript: self.network.transcript(starts_first),
+        };
+
+        let my_signature = self.network.identity().sign(&result.signing_bytes()?);
+        self.send(&GameMessage::ResultSignature {
+            signature: my_signature,
+        })?;
+
+        let their_signature = match self.receive()? {
+            GameMessage::ResultSignature { signature } => signature,
+            GameMessage::Error { message } => {
+                anyhow::bail!("Opponent refused to sign the result: {}", message)
+            }
+            _ => anyhow::bail!("Expected ResultSignature message"),
+        };
+
+        let signatures = if starts_first {
+            [my_signature, their_signature]
+        } else {
+            [their_signature, my_signature]
+        };
+        let signed = SignedGameResult { result, signatures };
+        signed
+            .verify()
+            .map_err(|e| anyhow::anyhow!("Opponent signed a different result: {}", e))?;
+        Ok(signed)
+    }
+
+    /// Prove our whole fleet is sunk and hand the win to the opponent
+    fn concede(&mut self, input: &BoardInput) -> anyhow::Result<()> {
+        say!(self, "🔐 Generating ZK proof that your fleet is sunk...");
+
+        let request = ProofRequest::FleetSunk(input.clone());
+        let receipt = self.prove("Proving your fleet is sunk", &request)?;
+
+        self.send(&GameMessage::GameOver {
+            winner: self.opponent_name.clone(),
+            proof: BoardProof::from_receipt(&receipt)?,
+        })?;
+        Ok(())
+    }
+
+    /// Wait for the opponent to prove the shot we just landed sank their last ship
+    fn await_concession(&mut self) -> anyhow::Result<Event> {
+        say!(self, "⏳ Waiting for opponent's proof that their fleet is sunk...");
+
+        match self.receive()? {
+            GameMessage::GameOver { winner, proof } => self.verify_concession(&winner, &proof),
+            GameMessage::Error { message } => anyhow::bail!("Opponent aborted the game: {}", message),
+            _ => anyhow::bail!("⚠️  CHEATING DETECTED: opponent did not concede with every ship sunk"),
+        }
+    }
+
+    /// Check a `GameOver` receipt proves some fleet sunk and names us the winner;
+    /// the match checks it is the opponent's current board
+    fn verify_concession(&self, winner: &str, proof: &BoardProof) -> anyhow::Result<Event> {
+        say!(self, "🔐 Verifying opponent's fleet-sunk proof...");
+
+        let receipt = proof
+            .to_receipt()
+            .map_err(|e| anyhow::anyhow!("⚠️  CHEATING DETECTED: malformed game over proof: {}", e))?;
+
+        self.verify_receipt(&receipt, proof.receipt_bytes.len(), self.peer_ids.fleet_sunk)
+            .map_err(|e| anyhow::anyhow!("⚠️  CHEATING DETECTED: forged game over proof ({})", e))?;
+
+        if winner != self.player_name {
+            anyhow::bail!("⚠️  CHEATING DETECTED: game over names {} as the winner", winner);
+        }
+        Ok(Event::Conceded(receipt.journal.decode()?))
+    }
+
+    /// Exchange initial boards and replay the opponent's proven rounds against theirs
+    fn reveal_and_audit(&mut self) -> anyhow::Result<()> {
+        say!(self, "\n🔍 Revealing boards for the post-game audit...");
+
+        self.send(&GameMessage::RevealBoard {
+            state: self.game.my_initial_state().clone(),
+        })?;
+
+        let revealed = match self.receive()? {
+            GameMessage::RevealBoard { state } => state,
+            GameMessage::Error { message } => {
+                anyhow::bail!("Opponent refused to reveal their board: {}", message)
+            }
+            _ => anyhow::bail!("Expected RevealBoard message"),
+        };
+
+        match self.game.audit(&revealed) {
+            Ok(_) => {
+                say!(
+                    self,
+                    "✅ Audit passed: {}'s board matches all {} proven turns.",
+                    self.opponent_name,
+                    self.game.turns_verified()
+                );
+                Ok(())
+            }
+            Err(e) => {
+                say!(self, "\n⚠️  AUDIT FAILED: {}", e);
+                anyhow::bail!("{}'s revealed board does not match the game: {}", self.opponent_name, e)
+            }
+        }
+    }
+
+    /// What the frontend shows between moves
+    fn view(&self) -> View<'_> {
+        View {
+            player_name: &self.player_name,
+            opponent_name: &self.opponent_name,
+            trust: self.policy.trust_level(),
+            my_turn: *self.game.phase() == Phase::MyTurn,
+            mine: &self.my_display,
+            opponent: &self.opponent_display,
+            fleet: self.game.my_state(),
+        }
+    }
+
+    /// Ask whoever plays this side for this turn's shots
+    fn choose_shots(&mut self, count: usize) -> anyhow::Result<Event> {
+        self.frontend.show_turn(&self.view());
+
+        if self.game.rules().mode == GameMode::Classic {
+            self.frontend.banner("TAKE YOUR SHOT");
+            return Ok(Event::Fire(vec![self.choose_shot(&[])?]));
+        }
+
+        self.frontend.banner("FIRE YOUR SALVO");
+        say!(self, "You have {} ships afloat, so you fire {} shots.", count, count);
+        let mut shots: Vec<Position> = Vec::with_capacity(count);
+        while shots.len() < count {
+            say!(self, "Shot {} of {}", shots.len() + 1, count);
+            let shot = self.choose_shot(&shots)?;
+            if shots.contains(&shot) {
+                say!(self, "{} is already in this salvo", shot);
+                continue;
+            }
+            shots.push(shot);
+        }
+        Ok(Event::Fire(shots))
+    }
+
+    /// Wait for the proven result of the shots we sent
+    fn receive_result(&mut self) -> anyhow::Result<Event> {
+        say!(self, "⏳ Waiting for ZK proof from opponent...");
+
+        match self.receive()? {
             GameMessage::ShotResult { position, hit_type, proof } => {
-                println!("🔐 Verifying ZK proof...");
-                
-                self.verify_shot_proof(position, &hit_type, &proof)?;
-                self.opponent_display.record_shot(position, hit_type.clone());
-                
-                println!("✅ Proof verified!");
-                
-                Ok(hit_type)
-            }
-            GameMessage::GameOver { winner } => {
-                println!("\n{} wins!", winner);
-                std::process::exit(0);
+                say!(self, "🔐 Verifying ZK proof...");
+                let receipt = proof.to_receipt()?;
+                // The policy agreed in the handshake decides which receipts are acceptable
+                self.verify_receipt(&receipt, proof.receipt_bytes.len(), self.peer_ids.round)?;
+                Ok(Event::Result {
+                    shots: vec![position],
+                    hits: vec![hit_type],
+                    proven: Proven::Round(receipt.journal.decode()?),
+                })
             }
+            GameMessage::SalvoResult { positions, hit_types, proof } => {
+                say!(self, "🔐 Verifying ZK proof...");
+                let receipt = proof.to_receipt()?;
+                self.verify_receipt(&receipt, proof.receipt_bytes.len(), self.peer_ids.salvo)?;
+                Ok(Event::Result {
+                    shots: positions,
+                    hits: hit_types,
+                    proven: Proven::Salvo(receipt.journal.decode()?),
+                })
+            }
+            GameMessage::GameOver { winner, proof } => self.verify_concession(&winner, &proof),
+            GameMessage::Error { message } => anyhow::bail!("Opponent rejected the shot: {}", message),
             _ => anyhow::bail!("Unexpected message"),
         }
     }
 
-    fn respond_to_shot(&mut self) -> anyhow::Result<HitType> {
-        println!("\nWaiting for opponent's shot...");
-        
-        match self.network.receive()? {
-            GameMessage::TakeShot { position } => {
-                println!("Opponent shot at {}", position);
-                println!("🔐 Generating ZK proof of result...");
-                
-                let (hit_type, proof) = self.generate_shot_proof(position)?;
-                
-                self.network.send(&GameMessage::ShotResult {
-                    position,
-                    hit_type: hit_type.clone(),
-                    proof,
-                })?;
-                
-                self.my_display.record_shot(position, hit_type.clone());
-                
-                println!("✅ Proof sent!");
-                
-                Ok(hit_type)
+    /// Show the verified result of our shots and what happens next
+    fn show_our_result(&mut self, shots: &[Position], hits: &[HitType]) {
+        for (pos, hit) in shots.iter().zip(hits) {
+            self.opponent_display.record_shot(*pos, hit.clone());
+        }
+        say!(self, "✅ ZK Proof verified! Results are cryptographically proven.");
+        self.frontend.show_result(&self.view(), true, shots, hits);
+
+        // With their last ship down the concession comes next
+        if self.game.opponent_afloat() == 0 {
+            return;
+        }
+        match (self.game.rules().mode, &hits[0]) {
+            (GameMode::Salvo, _) => say!(self, "\nTurn passes to opponent.\n"),
+            (GameMode::Classic, HitType::Miss) => {
+                say!(self, "\nYou missed! Turn passes to opponent.\n")
             }
-            GameMessage::GameOver { winner } => {
-                println!("\n{} wins!", winner);
-                std::process::exit(0);
+            (GameMode::Classic, HitType::Repeat) => {
+                say!(self, "\nAlready fired there! Turn passes to opponent.\n")
             }
+            (GameMode::Classic, HitType::Hit) => say!(self, "\nHIT! You get another shot!\n"),
+            (GameMode::Classic, HitType::Sunk(_)) => {
+                say!(self, "\nSHIP SUNK! You get another shot!\n")
+            }
+        }
+        self.pause();
+    }
+
+    /// Wait for the opponent to fire at us
+    fn receive_shots(&mut self) -> anyhow::Result<Event> {
+        self.frontend.show_turn(&self.view());
+
+        match self.game.rules().mode {
+            GameMode::Classic => say!(self, "\nWaiting for opponent's shot..."),
+            GameMode::Salvo => say!(self, "\nWaiting for opponent's salvo..."),
+        }
+        match self.receive()? {
+            GameMessage::TakeShot { position } => {
+                say!(self, "Opponent shot at {}", position);
+                Ok(Event::Incoming(vec![position]))
+            }
+            GameMessage::TakeShots { positions } => Ok(Event::Incoming(positions)),
+            GameMessage::GameOver { winner, proof } => self.verify_concession(&winner, &proof),
             _ => anyhow::bail!("Unexpected message"),
         }
     }
 
-    fn generate_shot_proof(&mut self, shot: Position) -> anyhow::Result<(HitType, ProofData)> {
-        let input = RoundInput {
-            state: self.my_state.clone(),
-            shot,
+    fn prove_round(&mut self, input: &RoundInput, commit: RoundCommit) -> anyhow::Result<()> {
+        say!(self, "🔐 Generating ZK proof of result...");
+
+        let request = ProofRequest::Round(input.clone());
+        let receipt = self.prove("Proving the shot result", &request)?;
+        if receipt.journal.decode::<RoundCommit>()? != commit {
+            anyhow::bail!("The prover returned a receipt for a different shot result");
+        }
+
+        let (shot, hit) = (commit.shot, commit.hit.clone());
+        let result = GameMessage::ShotResult {
+            position: shot,
+            hit_type: hit.clone(),
+            proof: ProofData::from_receipt(receipt, commit)?,
         };
-        
-        let old_commit = self.my_commitment;
-        let hit_type = self.my_state.apply_shot(shot);
-        let new_commit = self.my_state.commit();
-        
-        self.my_commitment = new_commit;
-        
-        let env = ExecutorEnv::builder().write(&input)?.build()?;
-        let prover = default_prover();
-        let prove_info = prover.prove(env, ROUND_ELF)?;
-        
-        let commit = RoundCommit {
-            old_state: old_commit,
-            new_state: new_commit,
-            shot,
-            hit: hit_type.clone(),
+        self.send_result(result, &[shot], &[hit])
+    }
+
+    fn prove_salvo(&mut self, input: &SalvoInput, commit: SalvoCommit) -> anyhow::Result<()> {
+        say!(self, "🔐 Generating ZK proof of {} results...", input.shots.len());
+
+        let request = ProofRequest::Salvo(input.clone());
+        let receipt = self.prove("Proving the salvo results", &request)?;
+        if receipt.journal.decode::<SalvoCommit>()? != commit {
+            anyhow::bail!("The prover returned a receipt for different salvo results");
+        }
+
+        let (shots, hits) = (commit.shots.clone(), commit.hits.clone());
+        let result = GameMessage::SalvoResult {
+            positions: shots.clone(),
+            hit_types: hits.clone(),
+            proof: SalvoProofData::from_receipt(receipt, commit)?,
         };
-        
-        let proof = ProofData::from_receipt(prove_info.receipt, commit)?;
-        
-        Ok((hit_type, proof))
-    }
-
-    fn verify_shot_proof(
-    &mut self,
-    position: Position,
-    hit_type: &HitType,
-    proof: &ProofData,
-) -> anyhow::Result<()> {
-    let receipt = proof.to_receipt()?;
-    
-    // In dev mode, skip image ID verification to allow cross-machine play
-    // In production, this MUST be enabled for security
-    if std::env::var("RISC0_DEV_MODE").is_ok() {
-        println!("🙈  DEV MODE: Skipping strict image ID verification");
-        // Dev mode: The proof was already generated in dev mode, so we just
-        // verify the receipt structure without checking the specific image ID
-        // This allows different machines with slightly different builds to play together
-    } else {
-        // Production mode: Strict verification with image ID check
-        receipt.verify(ROUND_ID)?;
-    }
-    
-    let commit: RoundCommit = receipt.journal.decode()?;
-    
-    if commit.old_state != self.opponent_commitment {
-        anyhow::bail!("Proof uses wrong state commitment!");
-    }
-    if commit.shot != position {
-        anyhow::bail!("Proof is for wrong shot position!");
-    }
-    if &commit.hit != hit_type {
-        anyhow::bail!("Proof hit type doesn't match!");
-    }
-    
-    self.opponent_commitment = commit.new_state;
-    
-    println!("✅ ZK Proof verified! Result is cryptographically proven.");
-    Ok(())
-}
+        self.send_result(result, &shots, &hits)
+    }
 
-    fn prompt_shot(&self) -> anyhow::Result<Position> {
-        loop {
-            print!("Enter coordinates to fire (x,y): ");
-            io::stdout().flush()?;
-            
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
-            
-            let parts: Vec<&str> = input.trim().split(',').collect();
-            if parts.len() != 2 {
-                println!("Invalid format. Use: x,y");
-                continue;
+    /// Send our proven result for the opponent's shots and show what happens next
+    fn send_result(
+        &mut self,
+        result: GameMessage,
+        shots: &[Position],
+        hits: &[HitType],
+    ) -> anyhow::Result<()> {
+        for (pos, hit) in shots.iter().zip(hits) {
+            self.my_display.record_shot(*pos, hit.clone());
+        }
+
+        // Saved before sending, so a lost result can be resent on resume
+        self.record(true, &result)?;
+        self.last_result = Some(result.clone());
+        self.save()?;
+        self.network.send(&result)?;
+        say!(self, "✅ Proof sent!");
+
+        self.frontend.show_result(&self.view(), false, shots, hits);
+        // Our last ship is down, so the concession comes next
+        if matches!(self.game.phase(), Phase::Finished { .. }) {
+            return Ok(());
+        }
+        match (self.game.rules().mode, &hits[0]) {
+            (GameMode::Salvo, _) => say!(self, "\nYour turn!\n"),
+            (GameMode::Classic, HitType::Miss | HitType::Repeat) => {
+                say!(self, "\nOpponent missed! Your turn!\n")
             }
-            
-            let x: u32 = match parts[0].trim().parse() {
-                Ok(v) if v < 10 => v,
-                _ => {
-                    println!("X must be 0-9");
-                    continue;
-                }
-            };
-            
-            let y: u32 = match parts[1].trim().parse() {
-                Ok(v) if v < 10 => v,
-                _ => {
-                    println!("Y must be 0-9");
-                    continue;
+            (GameMode::Classic, HitType::Hit) => {
+                say!(self, "\nOpponent hit! They shoot again...\n")
+            }
+            (GameMode::Classic, HitType::Sunk(_)) => {
+                say!(self, "\nOpponent sunk a ship! They shoot again...\n")
+            }
+        }
+        self.pause();
+        Ok(())
+    }
+
+    /// Prove our board is valid for this session with the init guest and send it
+    fn prove_board(&mut self, input: &BoardInput) -> anyhow::Result<()> {
+        say!(self, "\n🔐 Generating board commitment proof...");
+
+        let request = ProofRequest::Board(input.clone());
+        let receipt = self.prove("Proving your board", &request)?;
+        let proof = BoardProof::from_receipt(&receipt)?;
+
+        let commitment = self.game.my_commitment();
+        if receipt.journal.decode::<BoardCommit>()?.state != commitment {
+            anyhow::bail!("The prover returned a receipt for a different board");
+        }
+        say!(self, "✅ Your Board Commitment: {:?}", commitment);
+        say!(self, "\n🤝 Exchanging board commitments...");
+        self.send(&GameMessage::BoardReady {
+            commitment,
+            player_name: self.player_name.clone(),
+            proof,
+        })
+    }
+
+    /// Wait for the opponent's board and check its init receipt
+    fn receive_board(&mut self) -> anyhow::Result<Event> {
+        match self.receive()? {
+            GameMessage::BoardReady { commitment, player_name, proof } => {
+                say!(self, "✓ Received commitment from {}", player_name);
+                say!(self, "🔐 Verifying opponent's board proof...");
+
+                let proven = match self.verify_board_proof(&proof) {
+                    Ok(proven) => proven,
+                    Err(e) => {
+                        self.send(&GameMessage::Error {
+                            message: format!("Board proof rejected: {}", e),
+                        })?;
+                        anyhow::bail!("Opponent's board was rejected: {}", e);
+                    }
+                };
+
+                self.opponent_name = player_name;
+                if let Some(identity) = self.network.peer_identity() {
+                    say!(self, "🪪 {}'s identity: {}", self.opponent_name, fingerprint(identity.as_bytes()));
                 }
-            };
-            
-            return Ok(Position::new(x, y));
+                Ok(Event::OpponentBoard { commitment, proven })
+            }
+            GameMessage::Error { message } => {
+                anyhow::bail!("Opponent aborted the handshake: {}", message)
+            }
+            _ => anyhow::bail!("Expected BoardReady message"),
+        }
+    }
+
+    /// Check the opponent's init receipt proves some valid board; the match
+    /// checks it is for this session, these rules and the commitment sent
+    fn verify_board_proof(&self, proof: &BoardProof) -> anyhow::Result<BoardCommit> {
+        let receipt = proof
+            .to_receipt()
+            .map_err(|e| anyhow::anyhow!("malformed board receipt: {}", e))?;
+
+        self.verify_receipt(&receipt, proof.receipt_bytes.len(), self.peer_ids.init)
+            .map_err(|e| anyhow::anyhow!("receipt does not prove a valid fleet ({})", e))?;
+
+        Ok(receipt.journal.decode()?)
+    }
+
+    /// Verify one of the opponent's receipts under the agreed policy, as one of
+    /// the kinds we accept, and report its size and how long checking it took
+    fn verify_receipt(
+        &self,
+        receipt: &Receipt,
+        size: usize,
+        image_id: Digest,
+    ) -> anyhow::Result<()> {
+        ReceiptKind::check(receipt, &self.accepted_kinds)?;
+        let started = std::time::Instant::now();
+        self.policy.verify(receipt, image_id)?;
+
+        let kind = ReceiptKind::of(receipt).map_or("fake".to_string(), |kind| kind.to_string());
+        say!(
+            self,
+            "📦 {} receipt, {:.1} KiB, verified in {:.0} ms",
+            kind,
+            size as f64 / 1024.0,
+            started.elapsed().as_secs_f64() * 1000.0
+        );
+        Ok(())
+    }
+
+    /// The message that fires `shots` in this game's mode
+    fn shots_message(&self, shots: Vec<Position>) -> GameMessage {
+        match self.game.rules().mode {
+            GameMode::Classic => GameMessage::TakeShot { position: shots[0] },
+            GameMode::Salvo => GameMessage::TakeShots { positions: shots },
+        }
+    }
+
+    /// Persist the game as it stands after the last event
+    fn save(&self) -> anyhow::Result<()> {
+        if !self.saves {
+            return Ok(());
+        }
+
+        let (sent_chain, received_chain) = self.network.chains();
+        let peer_identity = self
+            .network
+            .peer_identity()
+            .ok_or_else(|| anyhow::anyhow!("Opponent never identified themselves"))?;
+
+        SavedGame {
+            game: self.game.clone(),
+            policy: self.policy.clone(),
+            peer_ids: self.peer_ids,
+            receipt_kind: self.receipt_kind,
+            accepted_kinds: self.accepted_kinds.clone(),
+            peer_identity,
+            player_name: self.player_name.clone(),
+            opponent_name: self.opponent_name.clone(),
+            my_display: self.my_display.clone(),
+            last_result: self.last_result.clone(),
+            opponent_display: self.opponent_display.clone(),
+            transcript_len: match &self.transcript {
+                Some(transcript) => transcript.bytes_written()?,
+                None => 0,
+            },
+            sent_chain,
+            received_chain,
+        }
+        .store()
+    }
+
+    /// Send a message, adding it to the transcript first
+    fn send(&mut self, message: &GameMessage) -> anyhow::Result<()> {
+        self.record(true, message)?;
+        self.network.send(message)
+    }
+
+    /// Receive a message, adding it to the transcript before it is checked
+    fn receive(&mut self) -> anyhow::Result<GameMessage> {
+        self.frontend.status(&format!("⏳ Waiting for {}", self.opponent_name_or_default()));
+        let message = self.network.receive()?;
+        self.record(false, &message)?;
+        Ok(message)
+    }
+
+    fn record(&mut self, sent: bool, message: &GameMessage) -> anyhow::Result<()> {
+        // Player 0 is whoever moved first
+        let player = if sent == self.game.starts_first() { 0 } else { 1 };
+        match &mut self.transcript {
+            Some(transcript) => transcript.record(player, message),
+            None => Ok(()),
+        }
+    }
+
+    /// Start proving the results of the opponent's likeliest next shots
+    fn speculate(&self) {
+        let Some(speculation) = &self.speculation else {
+            return;
+        };
+        if self.game.rules().mode != GameMode::Classic {
+            return;
+        }
+        let guesses = ai::likely_shots(&self.my_display, speculation.capacity());
+        let requests = guesses
+            .into_iter()
+            .map(|shot| {
+                ProofRequest::Round(RoundInput {
+                    session: self.game.session(),
+                    turn: self.game.turns_proven(),
+                    state: self.game.my_state().clone(),
+                    shot,
+                })
+            })
+            .collect();
+        speculation.speculate(requests, self.receipt_kind);
+    }
+
+    /// Prove with the configured backend, showing progress and how long it took.
+    ///
+    /// Whatever backend proved it, the receipt must be one our own policy
+    /// accepts, of the agreed kind, before anything is sent on.
+    fn prove(&self, what: &str, request: &ProofRequest) -> anyhow::Result<Receipt> {
+        self.frontend.status(&format!("🔐 {}...", what));
+        let started = std::time::Instant::now();
+        let receipt = self.prover.prove(request, self.receipt_kind)?;
+        self.frontend.proved(what, started.elapsed());
+
+        ReceiptKind::check(&receipt, &[self.receipt_kind])
+            .and_then(|()| self.policy.verify(&receipt, request.image_id()))
+            .map_err(|e| anyhow::anyhow!("The prover returned a bad receipt: {:#}", e))?;
+        Ok(receipt)
+    }
+
+    fn opponent_name_or_default(&self) -> &str {
+        if self.opponent_name.is_empty() {
+            "the opponent"
+        } else {
+            &self.opponent_name
+        }
+    }
+
+    /// Whether a person follows this side, so it keeps records
+    fn is_human(&self) -> bool {
+        !matches!(self.player, Player::Computer(_))
+    }
+
+    /// Give a human time to read the result; scripts and the computer do not wait
+    fn pause(&self) {
+        if matches!(self.player, Player::Human) {
+            self.frontend.pause();
+        }
+    }
+
+    /// Ask whoever plays this side for the next shot, avoiding `pending` salvo shots
+    fn choose_shot(&mut self, pending: &[Position]) -> anyhow::Result<Position> {
+        match &mut self.player {
+            Player::Human => self.frontend.choose_shot(&self.view(), pending),
+            Player::Automated(strategy) | Player::Computer(strategy) => {
+                Ok(strategy.next_shot(&self.opponent_display, pending))
+            }
+        }
+    }
+}
+
+/// Cut our copy of one direction's message chain back to what both players
+/// have; messages arrive in order, so the longer copy extends the shorter one
+fn agree_chain(mut chain: Vec<Digest>, theirs: ChainHead) -> anyhow::Result<Vec<Digest>> {
+    let len = theirs.len as usize;
+    if len == 0 {
+        anyhow::bail!("message history is empty");
+    }
+    if len <= chain.len() {
+        if chain[len - 1] != theirs.head {
+            anyhow::bail!("message histories have diverged");
+        }
+        chain.truncate(len);
+    }
+    Ok(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::keys::{Identity, TEST_HOME};
+    use crate::network::MemoryTransport;
+    use crate::prover::FakeProver;
+    use battleship_core::{FleetEntry, ShipClass};
+    use std::path::PathBuf;
+
+    fn small_rules(mode: GameMode) -> GameRules {
+        GameRules {
+            width: 5,
+            height: 5,
+            fleet: vec![FleetEntry {
+                class: ShipClass::new("Destroyer", 'D', 2),
+                count: 2,
+            }],
+            mode,
         }
     }
+
+    /// Play one side of a game as the computer
+    fn computer(
+        network: MemoryTransport,
+        starts_first: bool,
+        rules: GameRules,
+    ) -> anyhow::Result<()> {
+        computer_proving(network, starts_first, rules, Box::new(FakeProver))
+    }
+
+    fn computer_proving(
+        network: MemoryTransport,
+        starts_first: bool,
+        rules: GameRules,
+        prover: Box<dyn ShotProver>,
+    ) -> anyhow::Result<()> {
+        let policy = VerificationPolicy::DevFake;
+        let mut network = SignedTransport::new(network, Identity::generate(), starts_first);
+        let kinds = [ReceiptKind::Succinct, ReceiptKind::Composite];
+        let session =
+            GameCoordinator::negotiate(&mut network, starts_first, &policy, rules, &kinds)?;
+        let state = GameState::random(&session.rules, &mut rand::thread_rng()).unwrap();
+
+        let mut coordinator = GameCoordinator::new(
+            state,
+            network,
+            Player::Computer(Difficulty::Hard.strategy()),
+            format!("Computer {}", starts_first),
+            starts_first,
+            policy,
+            session,
+        )?
+        .with_prover(prover)
+        .with_speculation(4);
+        coordinator.handshake()?;
+        coordinator.play_game()
+    }
+
+    /// Play a whole game between two computers, proving and auditing every move
+    fn play_match(mode: GameMode) {
+        let (a, b) = MemoryTransport::pair();
+
+        let first = std::thread::spawn(move || computer(a, true, small_rules(mode)));
+        let second = computer(b, false, GameRules::classic());
+
+        first.join().unwrap().unwrap();
+        second.unwrap();
+    }
+
+    #[test]
+    fn test_classic_game() {
+        play_match(GameMode::Classic);
+    }
+
+    #[test]
+    fn test_salvo_game() {
+        play_match(GameMode::Salvo);
+    }
+
+    /// A backend that proves the result of the next cell over instead
+    struct Misdirected;
+
+    impl ShotProver for Misdirected {
+        fn prove(&self, request: &ProofRequest, kind: ReceiptKind) -> anyhow::Result<Receipt> {
+            let mut request = request.clone();
+            if let ProofRequest::Round(input) = &mut request {
+                let width = input.state.rules.width;
+                input.shot = Position::new((input.shot.x + 1) % width, input.shot.y);
+            }
+            FakeProver.prove(&request, kind)
+        }
+    }
+
+    #[test]
+    fn test_bad_prover_result() {
+        let (a, b) = MemoryTransport::pair();
+        let rules = small_rules(GameMode::Classic);
+        let honest = std::thread::spawn(move || computer(a, true, rules));
+        let rules = GameRules::classic();
+        let misled = computer_proving(b, false, rules, Box::new(Misdirected));
+
+        // The wrong result is caught before it reaches the opponent
+        let error = misled.unwrap_err().to_string();
+        assert!(error.contains("different shot result"), "{}", error);
+        let error = honest.join().unwrap().unwrap_err().to_string();
+        assert!(error.contains("left the game"), "{}", error);
+    }
+
+    /// A link that goes down after `sends` more messages
+    struct Dropping {
+        inner: Option<MemoryTransport>,
+        sends: usize,
+    }
+
+    impl Transport for Dropping {
+        fn send(&mut self, message: &GameMessage) -> anyhow::Result<()> {
+            if self.sends == 0 {
+                // Both directions go, so the opponent notices too
+                self.inner = None;
+            }
+            self.sends = self.sends.saturating_sub(1);
+            match &mut self.inner {
+                Some(inner) => inner.send(message),
+                None => anyhow::bail!("Connection dropped"),
+            }
+        }
+
+        fn receive(&mut self) -> anyhow::Result<GameMessage> {
+            match &mut self.inner {
+                Some(inner) => inner.receive(),
+                None => anyhow::bail!("Connection dropped"),
+            }
+        }
+    }
+
+    /// Play one side as a person would, in its own home, until the link drops
+    /// after `sends` messages; then resume from the save over `reconnect`
+    fn interrupted(
+        home: PathBuf,
+        link: MemoryTransport,
+        reconnect: MemoryTransport,
+        starts_first: bool,
+        sends: usize,
+    ) -> anyhow::Result<()> {
+        TEST_HOME.set(Some(home));
+        let identity = || Identity::load_or_generate(&key_dir().join("identity.key"));
+        let player = || Player::Automated(Difficulty::Hard.strategy());
+        let policy = VerificationPolicy::DevFake;
+
+        let link = Dropping { inner: Some(link), sends };
+        let mut network = SignedTransport::new(link, identity()?, starts_first);
+        let rules = small_rules(GameMode::Classic);
+        let kinds = [ReceiptKind::Succinct];
+        let session =
+            GameCoordinator::negotiate(&mut network, starts_first, &policy, rules, &kinds)?;
+        let id = session.id;
+        let state = GameState::random(&session.rules, &mut rand::thread_rng()).unwrap();
+
+        let name = format!("Player {}", starts_first);
+        let mut coordinator =
+            GameCoordinator::new(state, network, player(), name, starts_first, policy, session)?
+                .with_prover(Box::new(FakeProver));
+        let dropped = coordinator.handshake().and_then(|()| coordinator.play_game());
+        assert!(dropped.is_err(), "the game finished before the link dropped");
+        drop(coordinator);
+
+        let saved = SavedGame::load(&SavedGame::path(&id))?;
+        let network = SignedTransport::new(reconnect, identity()?, starts_first);
+        let mut coordinator = GameCoordinator::resume(saved, network, player())?
+            .with_prover(Box::new(FakeProver));
+        coordinator.play_game()?;
+        assert!(!SavedGame::path(&id).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resume_game() {
+        let home = std::env::temp_dir().join(format!("battleship-resume-{}", std::process::id()));
+        let homes = [home.join("first"), home.join("second")];
+
+        // After the handshake each side sends at least four shots or results,
+        // so the link goes down mid-game whichever side it cuts
+        for (cut, sends) in [(0, 4), (0, 5), (1, 4), (1, 5)] {
+            let limit = move |side| if side == cut { sends } else { usize::MAX };
+            let (a, b) = MemoryTransport::pair();
+            let (c, d) = MemoryTransport::pair();
+
+            let first_home = homes[0].clone();
+            let first =
+                std::thread::spawn(move || interrupted(first_home, a, c, true, limit(0)));
+            let second = interrupted(homes[1].clone(), b, d, false, limit(1));
+
+            first.join().unwrap().unwrap();
+            second.unwrap();
+        }
+        std::fs::remove_dir_all(home).unwrap();
+    }
+
+    #[test]
+    fn test_agree_chain() {
+        let chain: Vec<Digest> = (0u8..4).map(|i| Digest::from([i as u32; 8])).collect();
+
+        // The opponent is behind: drop what they never saw
+        let agreed = agree_chain(chain.clone(), ChainHead::of(&chain[..2])).unwrap();
+        assert_eq!(agreed, chain[..2]);
+
+        // The opponent is ahead: they will cut back to us
+        let agreed = agree_chain(chain[..3].to_vec(), ChainHead::of(&chain)).unwrap();
+        assert_eq!(agreed, chain[..3]);
+
+        let forked = ChainHead { len: 3, head: Digest::from([9u32; 8]) };
+        assert!(agree_chain(chain.clone(), forked).is_err());
+        assert!(agree_chain(chain, ChainHead { len: 0, head: Digest::default() }).is_err());
+    }
+
+    #[test]
+    fn test_negotiate_receipt_kinds() {
+        use ReceiptKind::*;
+
+        fn side(
+            network: MemoryTransport,
+            starts_first: bool,
+            kinds: &[ReceiptKind],
+        ) -> anyhow::Result<ReceiptKind> {
+            let mut network = SignedTransport::new(network, Identity::generate(), starts_first);
+            let policy = VerificationPolicy::DevFake;
+            let rules = GameRules::classic();
+            let session =
+                GameCoordinator::negotiate(&mut network, starts_first, &policy, rules, kinds)?;
+            Ok(session.receipt_kind)
+        }
+        let negotiate = |first: Vec<ReceiptKind>, second: Vec<ReceiptKind>| {
+            let (a, b) = MemoryTransport::pair();
+            let host = std::thread::spawn(move || side(a, true, &first));
+            let joined = side(b, false, &second);
+            (host.join().unwrap(), joined)
+        };
+
+        // The host's favourite of the kinds both accept wins on both sides
+        let (hosted, joined) = negotiate(vec![Groth16, Succinct], vec![Succinct, Groth16]);
+        assert_eq!(hosted.unwrap(), Groth16);
+        assert_eq!(joined.unwrap(), Groth16);
+
+        let (hosted, joined) = negotiate(vec![Composite], vec![Succinct, Groth16]);
+        assert!(hosted.is_err() && joined.is_err());
+    }
 }
@@ -0,0 +1,239 @@
+use crate::network_protocol::GameMessage;
+use crate::prover::{ProofRequest, ShotProver};
+use crate::transcript::Transcript;
+use crate::verification::{ImageIds, ReceiptKind, VerificationPolicy};
+use battleship_core::history::{HistoryCommit, HistoryIds, HistoryInput, HistoryStep};
+use battleship_core::GameMode;
+use battleship_guests::HISTORY_ID;
+use risc0_zkvm::Receipt;
+use std::path::{Path, PathBuf};
+
+/// The receipts one player proved about their board, in the order they were proven
+pub struct BoardReceipts {
+    pub mode: GameMode,
+    /// The init receipt for the board
+    pub init: Receipt,
+    /// One round or salvo receipt per turn the board was fired at
+    pub results: Vec<Receipt>,
+}
+
+impl BoardReceipts {
+    /// Player `player`'s receipts from a game transcript; player 0 moved first
+    pub fn from_transcript(transcript: &Transcript, player: usize) -> anyhow::Result<Self> {
+        let mut init = None;
+        let mut results = Vec::new();
+        for entry in transcript.entries.iter().filter(|entry| entry.player == player) {
+            match &entry.message {
+                GameMessage::BoardReady { proof, .. } => init = Some(proof.to_receipt()?),
+                GameMessage::ShotResult { proof, .. } => results.push(proof.to_receipt()?),
+                GameMessage::SalvoResult { proof, .. } => results.push(proof.to_receipt()?),
+                _ => {}
+            }
+        }
+
+        let Some(init) = init else {
+            anyhow::bail!("the transcript has no board proof from player {}", player);
+        };
+        Ok(Self {
+            mode: transcript.header.rules.mode,
+            init,
+            results,
+        })
+    }
+}
+
+/// Image IDs of the guests compiled into this binary, as the history guest takes them
+pub fn local_history_ids() -> HistoryIds {
+    let ids = ImageIds::local();
+    HistoryIds {
+        history: HISTORY_ID.into(),
+        init: ids.init,
+        round: ids.round,
+        salvo: ids.salvo,
+    }
+}
+
+/// Where the whole-game proof of a transcript's player is written
+pub fn path(transcript: &Path, player: usize) -> PathBuf {
+    transcript.with_extension(format!("player{}.history", player))
+}
+
+/// Fold a board's receipts into one history receipt, one proof per receipt.
+///
+/// Every step verifies the previous history receipt and the next result
+/// receipt inside the guest, so the last receipt stands for all of them.
+/// Steps are proven succinct, so each one carries the same two assumptions
+/// however long the game ran; only the last is proven as `kind`. Groth16
+/// receipts cannot be resolved as assumptions, so games played with them
+/// cannot be folded. `progress` is told how many of the steps are done.
+pub fn prove_history(
+    receipts: &BoardReceipts,
+    prover: &dyn ShotProver,
+    kind: ReceiptKind,
+    mut progress: impl FnMut(usize, usize),
+) -> anyhow::Result<Receipt> {
+    let all = std::iter::once(&receipts.init).chain(&receipts.results);
+    if all.into_iter().any(|receipt| ReceiptKind::of(receipt) == Some(ReceiptKind::Groth16)) {
+        anyhow::bail!(
+            "the game was proven with Groth16 receipts, which cannot be folded; \
+             only games played with composite or succinct receipts can be"
+        );
+    }
+
+    let ids = local_history_ids();
+    let steps = receipts.results.len() + 1;
+    let kind_of = |step: usize| if step == steps { kind } else { ReceiptKind::Succinct };
+
+    let input = HistoryInput {
+        ids,
+        step: HistoryStep::Start(receipts.init.journal.decode()?),
+    };
+    let request = ProofRequest::History {
+        input: Box::new(input),
+        assumptions: vec![receipts.init.clone()],
+    };
+    let mut history = prover.prove(&request, kind_of(1))?;
+    progress(1, steps);
+
+    for (i, result) in receipts.results.iter().enumerate() {
+        let previous: HistoryCommit = history.journal.decode()?;
+        let step = match receipts.mode {
+            GameMode::Classic => HistoryStep::Round {
+                previous,
+                round: result.journal.decode()?,
+            },
+            GameMode::Salvo => HistoryStep::Salvo {
+                previous,
+                salvo: result.journal.decode()?,
+            },
+        };
+        let request = ProofRequest::History {
+            input: Box::new(HistoryInput { ids, step }),
+            assumptions: vec![history, result.clone()],
+        };
+        history = prover
+            .prove(&request, kind_of(i + 2))
+            .map_err(|e| anyhow::anyhow!("turn {} does not continue the history: {}", i, e))?;
+        progress(i + 2, steps);
+    }
+    Ok(history)
+}
+
+/// Verify a history receipt under `policy` and check it was folded with
+/// guests the policy trusts, returning what it attests to
+pub fn verify_history(
+    receipt: &Receipt,
+    policy: &VerificationPolicy,
+) -> anyhow::Result<HistoryCommit> {
+    policy.verify(receipt, HISTORY_ID.into())?;
+    let history: HistoryCommit = receipt.journal.decode()?;
+
+    // The guest verified its assumptions against the IDs it was handed
+    if history.ids.history != HISTORY_ID.into() {
+        anyhow::bail!("the history was extended by a different history guest");
+    }
+    let builds = match policy {
+        VerificationPolicy::Pinned(pinned) => pinned.clone(),
+        _ => vec![ImageIds::local()],
+    };
+    let trusted = builds.iter().any(|ids| {
+        let folded = &history.ids;
+        ids.init == folded.init && ids.round == folded.round && ids.salvo == folded.salvo
+    });
+    if !trusted {
+        anyhow::bail!("the history rests on receipts from guests that are not accepted");
+    }
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prover::{FakeProver, LocalProver};
+    use battleship_core::{BoardInput, GameRules, GameState, RoundInput};
+    use battleship_guests::ROUND_ID;
+    use risc0_zkvm::sha::Digest;
+    use risc0_zkvm::{Groth16Receipt, InnerReceipt, ReceiptClaim};
+    use std::sync::Mutex;
+
+    /// Prove a board and the results of its first `turns` shots
+    fn played(prover: &dyn ShotProver, turns: usize) -> (BoardReceipts, GameState) {
+        let rules = GameRules::classic();
+        let mut state = GameState::random(&rules, &mut rand::thread_rng()).unwrap();
+        let session = Digest::from([5u32; 8]);
+        let kind = ReceiptKind::Succinct;
+        let board = BoardInput { session, state: state.clone() };
+        let init = prover.prove(&ProofRequest::Board(board), kind).unwrap();
+
+        let mut results = Vec::new();
+        for (turn, shot) in rules.positions().take(turns).enumerate() {
+            let input = RoundInput { session, turn: turn as u32, state: state.clone(), shot };
+            results.push(prover.prove(&ProofRequest::Round(input), kind).unwrap());
+            state.apply_shot(shot).unwrap();
+        }
+        (BoardReceipts { mode: GameMode::Classic, init, results }, state)
+    }
+
+    /// Fake proves, noting the kind asked for each time
+    #[derive(Default)]
+    struct Kinds(Mutex<Vec<ReceiptKind>>);
+
+    impl ShotProver for Kinds {
+        fn prove(&self, request: &ProofRequest, kind: ReceiptKind) -> anyhow::Result<Receipt> {
+            self.0.lock().unwrap().push(kind);
+            FakeProver.prove(request, kind)
+        }
+    }
+
+    #[test]
+    fn test_history_proof() {
+        let (mut receipts, state) = played(&FakeProver, 12);
+        let kinds = Kinds::default();
+        let mut steps = Vec::new();
+        let history = prove_history(&receipts, &kinds, ReceiptKind::Groth16, |done, of| {
+            steps.push((done, of))
+        })
+        .unwrap();
+        assert_eq!(steps.last(), Some(&(13, 13)));
+
+        // Only the last step is wrapped as Groth16, the rest stay succinct
+        let kinds = kinds.0.into_inner().unwrap();
+        assert_eq!(kinds.len(), 13);
+        assert!(kinds[..12].iter().all(|kind| *kind == ReceiptKind::Succinct));
+        assert_eq!(kinds[12], ReceiptKind::Groth16);
+
+        let proven = verify_history(&history, &VerificationPolicy::DevFake).unwrap();
+        assert_eq!(proven.state, state.commit());
+        assert_eq!(proven.turns, 12);
+        assert!(verify_history(&history, &VerificationPolicy::Strict).is_err());
+
+        // Groth16 receipts cannot be assumptions, so they are refused up front
+        let honest = receipts.results[5].clone();
+        let claim = ReceiptClaim::ok(ROUND_ID, honest.journal.bytes.clone());
+        let seal = Groth16Receipt::new(vec![], claim.into(), Digest::default());
+        receipts.results[5].inner = InnerReceipt::Groth16(seal);
+        let error = prove_history(&receipts, &FakeProver, ReceiptKind::Succinct, |_, _| {});
+        assert!(error.unwrap_err().to_string().contains("Groth16"));
+        receipts.results[5] = honest;
+
+        // A result left out breaks the chain
+        receipts.results.remove(3);
+        assert!(prove_history(&receipts, &FakeProver, ReceiptKind::Succinct, |_, _| {}).is_err());
+    }
+
+    /// Runs the real guests, so each history step has to verify the receipts
+    /// it was handed. In dev mode the executor still checks every assumption
+    /// the guest verifies against the receipts given, but skips the recursion
+    /// that resolves them; without it this proves everything for real.
+    #[test]
+    #[ignore = "needs r0vm and the built guests; run with RISC0_DEV_MODE=1 and --ignored"]
+    fn test_local_history_proof() {
+        let policy = VerificationPolicy::from_env().unwrap();
+        let (receipts, state) = played(&LocalProver, 3);
+        let history =
+            prove_history(&receipts, &LocalProver, ReceiptKind::Succinct, |_, _| {}).unwrap();
+        let proven = verify_history(&history, &policy).unwrap();
+        assert_eq!(proven.state, state.commit());
+        assert_eq!(proven.turns, 3);
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::Context;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use risc0_zkvm::sha::{Impl, Sha256};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+/// Noise protocol used for the encrypted peer channel
+pub const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+
+#[cfg(test)]
+thread_local! {
+    /// Gives each player thread of a test its own home, which the process-wide
+    /// `BATTLESHIP_HOME` cannot
+    pub static TEST_HOME: std::cell::RefCell<Option<PathBuf>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Where a player's keys live: `BATTLESHIP_HOME`, or `~/.battleship`
+pub fn key_dir() -> PathBuf {
+    #[cfg(test)]
+    if let Some(dir) = TEST_HOME.with_borrow(|dir| dir.clone()) {
+        return dir;
+    }
+    if let Ok(dir) = std::env::var("BATTLESHIP_HOME") {
+        return PathBuf::from(dir);
+    }
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    Path::new(&home).join(".battleship")
+}
+
+/// Short, human-comparable fingerprint of a public key
+pub fn fingerprint(public: &[u8]) -> String {
+    let digest = Impl::hash_bytes(public);
+    let hex = hex::encode(digest.as_bytes());
+    hex.as_bytes()[..32]
+        .chunks(4)
+        .map(|group| std::str::from_utf8(group).expect("hex is ascii"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A player's static X25519 key for the encrypted channel
+pub struct NoiseKeys {
+    pub private: Vec<u8>,
+    pub public: Vec<u8>,
+}
+
+impl NoiseKeys {
+    pub fn generate() -> anyhow::Result<Self> {
+        let keypair = snow::Builder::new(NOISE_PARAMS.parse()?).generate_keypair()?;
+        Ok(Self {
+            private: keypair.private,
+            public: keypair.public,
+        })
+    }
+
+    /// Load the key stored at `path`, creating it on first use
+    pub fn load_or_generate(path: &Path) -> anyhow::Result<Self> {
+        if path.exists() {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read key from {}", path.display()))?;
+            let mut lines = contents.lines().map(hex::decode);
+            let (Some(Ok(private)), Some(Ok(public))) = (lines.next(), lines.next()) else {
+                anyhow::bail!("{} is not a valid key file", path.display());
+            };
+            return Ok(Self { private, public });
+        }
+
+        let keys = Self::generate()?;
+        let contents = format!("{}\n{}\n", hex::encode(&keys.private), hex::encode(&keys.public));
+        write_private(path, &contents)?;
+        println!("🔑 Generated a new channel key in {}", path.display());
+        Ok(keys)
+    }
+
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.public)
+    }
+}
+
+/// A player's long-term Ed25519 identity; every message they send is signed with it
+pub struct Identity {
+    signing: SigningKey,
+}
+
+impl Identity {
+    /// A throwaway identity, for the computer opponent
+    pub fn generate() -> Self {
+        Self {
+            signing: SigningKey::generate(&mut rand::rngs::OsRng),
+        }
+    }
+
+    /// Load the identity stored at `path`, creating it on first use
+    pub fn load_or_generate(path: &Path) -> anyhow::Result<Self> {
+        if path.exists() {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read identity from {}", path.display()))?;
+            let secret: [u8; 32] = hex::decode(contents.trim())
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| anyhow::anyhow!("{} is not a valid identity file", path.display()))?;
+            return Ok(Self {
+                signing: SigningKey::from_bytes(&secret),
+            });
+        }
+
+        let identity = Self::generate();
+        write_private(path, &format!("{}\n", hex::encode(identity.signing.to_bytes())))?;
+        println!("🪪 Generated a new player identity in {}", path.display());
+        Ok(identity)
+    }
+
+    pub fn public(&self) -> VerifyingKey {
+        self.signing.verifying_key()
+    }
+
+    pub fn fingerprint(&self) -> String {
+        fingerprint(self.public().as_bytes())
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        self.signing.sign(message)
+    }
+}
+
+/// Write a secret file readable only by this user.
+///
+/// The contents go to a temporary file next to `path` that is then renamed
+/// over it, so a crash mid-write leaves the old file, never a torn one.
+pub fn write_private(path: &Path, contents: &str) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true).mode(0o600);
+
+    let mut file = options
+        .open(&temporary)
+        .with_context(|| format!("Failed to write {}", temporary.display()))?;
+    std::io::Write::write_all(&mut file, contents.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&temporary, path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,22 @@
+//! Networked zero-knowledge battleship: the game coordinator, transports and
+//! wire protocol shared by the `battleship` and `battleship-verify` binaries.
+
+pub mod ai;
+pub mod board_display;
+pub mod board_file;
+pub mod framing;
+pub mod frontend;
+pub mod game_coordinator;
+pub mod history;
+pub mod keys;
+pub mod network;
+pub mod network_protocol;
+pub mod prover;
+pub mod replay;
+pub mod rules_setup;
+pub mod saved_game;
+pub mod ship_placement;
+pub mod speculation;
+pub mod transcript;
+pub mod tui;
+pub mod verification;
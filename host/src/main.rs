@@ -1,97 +1,533 @@
-mod board_display;
-mod game_coordinator;
-mod network;
-mod network_protocol;
-mod ship_placement;
-
-use battleship_core::GameState;
-use battleship_guests::{INIT_ELF, INIT_ID};
-use game_coordinator::GameCoordinator;
-use network::NetworkConnection;
-use risc0_zkvm::{default_prover, ExecutorEnv};
-use std::io::{self, Write};
+mod cli;
+
+use battleship_core::GameRules;
+use battleship_host::ai::Difficulty;
+use battleship_host::game_coordinator::{GameCoordinator, Player};
+use battleship_host::history::{self, BoardReceipts};
+use battleship_host::keys::{self, Identity};
+use battleship_host::network::{
+    MemoryTransport, SignedTransport, StreamTransport, TcpTransport, Transport, UnixTransport,
+};
+use battleship_host::prover::{self, ProverConfig};
+use battleship_host::replay::Replay;
+use battleship_host::saved_game::SavedGame;
+use battleship_host::transcript::Transcript;
+use battleship_host::tui::Tui;
+use battleship_host::verification::{ReceiptKind, VerificationPolicy};
 use bytemuck::cast_slice;
+use clap::Parser;
+use cli::{Cli, Command, GameArgs, ListenArgs, RulesArgs, ShotArgs, DEFAULT_PORT};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 fn main() -> anyhow::Result<()> {
-
-    println!("🔍 DEBUG - Method IDs:");
-    println!(" INIT_ID: {}", hex::encode(cast_slice(&battleship_guests::INIT_ID)));
-    println!(" ROUND_ID: {}", hex::encode(cast_slice(&battleship_guests::ROUND_ID)));
-
+    let cli = Cli::parse();
 
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::filter::EnvFilter::from_default_env())
         .init();
 
+    let policy = match cli.policy {
+        Some(policy) => policy,
+        None => VerificationPolicy::from_env()?,
+    };
+    let prover = match cli.prover {
+        Some(prover) => prover,
+        None => ProverConfig::from_env()?,
+    };
+
+    match cli.command {
+        Some(Command::Verify { transcript }) => {
+            if !Transcript::load(&transcript)?.print_report(&policy) {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Some(Command::Replay { transcript }) => Replay::load(&transcript, &policy)?.run(),
+        Some(Command::Prover { listen, connections }) => {
+            serve_proofs(&listen, connections, &prover)
+        }
+        Some(Command::ProveGame { transcript, player, kind }) => {
+            prove_game(&transcript, player, kind, &policy, &prover)
+        }
+        Some(Command::VerifyGame { proof }) => verify_game(&proof, &policy),
+        command => {
+            let proving = Proving {
+                backend: prover,
+                speculate: cli.speculate,
+                receipts: cli.receipts,
+            };
+            play(command, policy, &proving, cli.tui)
+        }
+    }
+}
+
+fn play(
+    command: Option<Command>,
+    policy: VerificationPolicy,
+    proving: &Proving,
+    tui: bool,
+) -> anyhow::Result<()> {
+    println!("🔍 DEBUG - Method IDs:");
+    println!(" INIT_ID: {}", hex::encode(cast_slice(&battleship_guests::INIT_ID)));
+    println!(" ROUND_ID: {}", hex::encode(cast_slice(&battleship_guests::ROUND_ID)));
+
     println!("\n╔═══════════════════════════════════════════════╗");
     println!("║   ZERO-KNOWLEDGE BATTLESHIP - Multiplayer     ║");
     println!("║   Network Play with Cryptographic Proofs      ║");
     println!("╚═══════════════════════════════════════════════╝\n");
 
-    // Choose mode
+    policy.check_local()?;
+    proving.backend.check_policy(&policy)?;
+    proving.backend.check_kinds(&proving.receipts)?;
+    println!("Verification policy: {}", policy.trust_level());
+    println!("Prover: {}", proving.backend);
+    println!("Receipt kinds: {}", ReceiptKind::format_list(&proving.receipts));
+    if let Some(cache_size) = proving.speculate {
+        println!("Speculative proving: up to {} results ahead", cache_size);
+    }
+
+    let identity = Identity::load_or_generate(&keys::key_dir().join("identity.key"))?;
+    println!("Your identity: {}\n", identity.fingerprint());
+
+    let command = match command {
+        Some(command) => command,
+        None => choose_from_menu()?,
+    };
+
+    match command {
+        Command::Host { listen, rules, game } => {
+            let rules = rules.resolve(game.shots.automated())?;
+            let network = open_connection(&listen.bind, listen.port, true)?;
+            // Host goes first and picks the rules
+            play_new_game(network, true, rules, &game, policy, proving, identity, tui, None)
+        }
+        Command::Join { address, port, game } => {
+            let network = open_connection(&address, port, false)?;
+            let rules = GameRules::classic();
+            play_new_game(network, false, rules, &game, policy, proving, identity, tui, None)
+        }
+        Command::Solo { difficulty, rules, game } => {
+            let rules = rules.resolve(game.shots.automated())?;
+            let (network, computer_network) = MemoryTransport::pair();
+            let computer = spawn_computer(
+                computer_network,
+                difficulty,
+                policy.clone(),
+                proving.backend.clone(),
+                proving.receipts.clone(),
+                rules.clone(),
+            );
+            let network = Box::new(network);
+            let computer = Some(computer);
+            play_new_game(network, true, rules, &game, policy, proving, identity, tui, computer)
+        }
+        Command::Resume { save, address, port, shots } => {
+            resume_game(save, address, port, &shots, proving, identity, tui)
+        }
+        Command::Verify { .. }
+        | Command::Replay { .. }
+        | Command::Prover { .. }
+        | Command::ProveGame { .. }
+        | Command::VerifyGame { .. } => unreachable!("handled in main"),
+    }
+}
+
+/// The interactive menu, for when no subcommand is given
+fn choose_from_menu() -> anyhow::Result<Command> {
     println!("Choose mode:");
     println!("  1. Host a game (wait for opponent)");
     println!("  2. Join a game (connect to opponent)");
-    print!("\nEnter choice (1/2): ");
-    io::stdout().flush()?;
+    println!("  3. Play against the computer");
+    println!("  4. Resume a saved game");
 
-    let mut choice = String::new();
-    io::stdin().read_line(&mut choice)?;
-
-    let (network, starts_first) = match choice.trim() {
+    Ok(match prompt("\nEnter choice (1/2/3/4): ")?.as_str() {
         "1" => {
-            let network = NetworkConnection::host(7878)?;
-            (network, true) // Host goes first
-        }
-        "2" => {
-            print!("Enter opponent's IP address: ");
-            io::stdout().flush()?;
-            let mut ip = String::new();
-            io::stdin().read_line(&mut ip)?;
-            
-            let network = NetworkConnection::connect(ip.trim(), 7878)?;
-            (network, false) // Client goes second
+            let bind = prompt("Listen on (Enter for TCP port 7878, or unix:/path/to/socket): ")?;
+            Command::Host {
+                listen: ListenArgs {
+                    bind: if bind.is_empty() { "0.0.0.0".to_string() } else { bind },
+                    port: DEFAULT_PORT,
+                },
+                rules: RulesArgs::default(),
+                game: GameArgs::default(),
+            }
         }
+        "2" => Command::Join {
+            address: prompt("Enter opponent's IP address (or unix:/path/to/socket): ")?,
+            port: DEFAULT_PORT,
+            game: GameArgs::default(),
+        },
+        "3" => Command::Solo {
+            difficulty: prompt_difficulty()?,
+            rules: RulesArgs::default(),
+            game: GameArgs::default(),
+        },
+        "4" => Command::Resume {
+            save: None,
+            address: None,
+            port: DEFAULT_PORT,
+            shots: ShotArgs::default(),
+        },
         _ => anyhow::bail!("Invalid choice"),
-    };
+    })
+}
 
-    // Get player name
-    print!("\nEnter your name: ");
-    io::stdout().flush()?;
-    let mut player_name = String::new();
-    io::stdin().read_line(&mut player_name)?;
-    let player_name = player_name.trim().to_string();
+/// How this player's proofs are made
+struct Proving {
+    backend: ProverConfig,
+    /// Cache size for speculative proving, when it is on
+    speculate: Option<usize>,
+    /// Receipt kinds we accept and prove, favourite first
+    receipts: Vec<ReceiptKind>,
+}
+
+impl Proving {
+    fn apply<T: Transport>(&self, coordinator: GameCoordinator<T>) -> GameCoordinator<T> {
+        let coordinator = coordinator.with_prover(self.backend.build());
+        match self.speculate {
+            Some(cache_size) => coordinator.with_speculation(cache_size),
+            None => coordinator,
+        }
+    }
+}
+
+/// Negotiate the session, set up our fleet and play it out
+#[allow(clippy::too_many_arguments)]
+fn play_new_game(
+    network: Box<dyn Transport>,
+    starts_first: bool,
+    rules: GameRules,
+    game: &GameArgs,
+    policy: VerificationPolicy,
+    proving: &Proving,
+    identity: Identity,
+    tui: bool,
+    computer: Option<std::thread::JoinHandle<anyhow::Result<()>>>,
+) -> anyhow::Result<()> {
+    let mut network = SignedTransport::new(network, identity, starts_first);
+    println!("\n🤝 Negotiating game session...");
+    let session =
+        GameCoordinator::negotiate(&mut network, starts_first, &policy, rules, &proving.receipts)?;
+    println!("✓ Verification policy agreed: {}", policy.trust_level());
+    println!("✓ Receipts: {}", session.receipt_kind);
+    println!("✓ Session: {:?}", session.id);
+    println!(
+        "✓ Rules: {}x{} board, {} ships, {} mode",
+        session.rules.width,
+        session.rules.height,
+        session.rules.total_ships(),
+        session.rules.mode
+    );
+
+    let player_name = game.name()?;
 
     // Ship placement
     println!("\n📍 SHIP PLACEMENT");
-    let state = ship_placement::interactive_ship_placement()?;
-
-    // Generate ZK proof
-    println!("\n🔐 Generating board commitment proof...");
-    let commitment = prove_board_init(&state)?;
-    println!("✅ Your Board Commitment: {:?}", commitment);
+    let tui = if tui { start_tui() } else { None };
+    let state = game.place(&session.rules, tui.as_ref())?;
 
-    // Start game
-    let mut coordinator = GameCoordinator::new(
+    // Start game; the board proof is generated once the session is negotiated
+    let coordinator = GameCoordinator::new(
         state,
-        commitment,
         network,
+        game.shots.player()?,
         player_name,
         starts_first,
-    );
+        policy,
+        session,
+    )?;
+    let mut coordinator = proving.apply(coordinator);
+    if computer.is_some() {
+        coordinator = coordinator.without_saves();
+    }
+    if let Some(tui) = tui {
+        coordinator = coordinator.with_frontend(tui);
+    }
 
     coordinator.handshake()?;
-    coordinator.play_game()?;
+    let played = coordinator.play_game();
+    // Leave the full-screen UI before printing anything more
+    drop(coordinator);
+    if let Err(e) = played {
+        if computer.is_none() {
+            println!("\n💾 The game was saved; run `battleship resume` to continue it");
+        }
+        return Err(e);
+    }
+
+    if let Some(computer) = computer {
+        computer
+            .join()
+            .map_err(|_| anyhow::anyhow!("The computer opponent crashed"))??;
+    }
+
+    println!("\n🎮 Game Over! Thanks for playing!\n");
+    Ok(())
+}
+
+/// Pick a saved game and reconnect to the opponent: whoever went first listens again
+fn resume_game(
+    save: Option<PathBuf>,
+    address: Option<String>,
+    port: u16,
+    shots: &ShotArgs,
+    proving: &Proving,
+    identity: Identity,
+    tui: bool,
+) -> anyhow::Result<()> {
+    let saved = match save {
+        Some(path) => SavedGame::load(&path)?,
+        None => choose_saved_game(shots.automated())?,
+    };
+
+    let address = match address {
+        Some(address) => address,
+        None if saved.game.starts_first() && shots.automated() => "0.0.0.0".to_string(),
+        None if saved.game.starts_first() => {
+            let bind = prompt("Listen on (Enter for TCP port 7878, or unix:/path/to/socket): ")?;
+            if bind.is_empty() { "0.0.0.0".to_string() } else { bind }
+        }
+        None => prompt("Enter opponent's IP address (or unix:/path/to/socket): ")?,
+    };
+    let network = open_connection(&address, port, saved.game.starts_first())?;
+
+    let network = SignedTransport::new(network, identity, saved.game.starts_first());
+    let coordinator = GameCoordinator::resume(saved, network, shots.player()?)?;
+    let mut coordinator = proving.apply(coordinator);
+    if let Some(tui) = if tui { start_tui() } else { None } {
+        coordinator = coordinator.with_frontend(tui);
+    }
+    let played = coordinator.play_game();
+    drop(coordinator);
+    if let Err(e) = played {
+        println!("\n💾 The game was saved; run `battleship resume` to continue it");
+        return Err(e);
+    }
 
     println!("\n🎮 Game Over! Thanks for playing!\n");
     Ok(())
 }
 
-fn prove_board_init(state: &GameState) -> anyhow::Result<risc0_zkvm::sha::Digest> {
-    let env = ExecutorEnv::builder().write(state)?.build()?;
-    let prover = default_prover();
-    let prove_info = prover.prove(env, INIT_ELF)?;
-    prove_info.receipt.verify(INIT_ID)?;
-    let commitment = prove_info.receipt.journal.decode()?;
-    Ok(commitment)
-}
\ No newline at end of file
+/// List the saved games and let the player pick one; automated runs take the most recent
+fn choose_saved_game(automated: bool) -> anyhow::Result<SavedGame> {
+    let saves = SavedGame::list()?;
+    if saves.is_empty() {
+        anyhow::bail!("No saved games in {}", keys::key_dir().join("saves").display());
+    }
+
+    let saves = saves
+        .iter()
+        .map(|path| SavedGame::load(path))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if automated {
+        return Ok(saves.into_iter().next().expect("there is a saved game"));
+    }
+
+    println!("\nSaved games:");
+    for (i, saved) in saves.iter().enumerate() {
+        println!(
+            "  {}. {} vs {} ({}x{}, {} mode, {} hits scored)",
+            i + 1,
+            saved.player_name,
+            saved.opponent_name,
+            saved.game.rules().width,
+            saved.game.rules().height,
+            saved.game.rules().mode,
+            saved.opponent_display.hit_count()
+        );
+    }
+
+    let choice = loop {
+        match prompt(&format!("\nEnter choice (1-{}): ", saves.len()))?.parse::<usize>() {
+            Ok(n) if (1..=saves.len()).contains(&n) => break n - 1,
+            _ => println!("Invalid choice"),
+        }
+    };
+    Ok(saves.into_iter().nth(choice).expect("choice is in range"))
+}
+
+/// Switch to the full-screen UI, falling back to plain text when it cannot start
+fn start_tui() -> Option<Tui> {
+    match Tui::start() {
+        Ok(tui) => Some(tui),
+        Err(e) => {
+            println!("⚠️  Cannot start the full-screen UI ({}), using plain text", e);
+            None
+        }
+    }
+}
+
+fn prompt_difficulty() -> anyhow::Result<Difficulty> {
+    println!("\nChoose difficulty:");
+    println!("  1. Easy   (random shots)");
+    println!("  2. Medium (hunts, then targets hits)");
+    println!("  3. Hard   (probability heat map)");
+
+    loop {
+        match prompt("\nEnter choice (1/2/3): ")?.as_str() {
+            "1" => return Ok(Difficulty::Easy),
+            "2" => return Ok(Difficulty::Medium),
+            "3" => return Ok(Difficulty::Hard),
+            _ => println!("Invalid choice"),
+        }
+    }
+}
+
+fn prompt(message: &str) -> anyhow::Result<String> {
+    print!("{}", message);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+/// Run the computer's side of the game on its own thread; it proves and
+/// verifies every move over the in-process connection just like a remote player
+fn spawn_computer(
+    network: MemoryTransport,
+    difficulty: Difficulty,
+    policy: VerificationPolicy,
+    prover: ProverConfig,
+    receipts: Vec<ReceiptKind>,
+    rules: GameRules,
+) -> std::thread::JoinHandle<anyhow::Result<()>> {
+    std::thread::spawn(move || {
+        let mut network = SignedTransport::new(network, Identity::generate(), false);
+        let session =
+            GameCoordinator::negotiate(&mut network, false, &policy, rules, &receipts)?;
+        let state = difficulty
+            .placement()
+            .place(&session.rules, &mut rand::thread_rng())?;
+
+        let mut coordinator = GameCoordinator::new(
+            state,
+            network,
+            Player::Computer(difficulty.strategy()),
+            format!("Computer ({})", difficulty),
+            false,
+            policy,
+            session,
+        )?
+        .with_prover(prover.build());
+        coordinator.handshake()?;
+        coordinator.play_game()
+    })
+}
+
+/// Check a transcript, then fold each chosen player's receipts into a whole-game proof
+fn prove_game(
+    path: &Path,
+    player: Option<usize>,
+    kind: ReceiptKind,
+    policy: &VerificationPolicy,
+    backend: &ProverConfig,
+) -> anyhow::Result<()> {
+    backend.check_kinds(&[kind])?;
+    let transcript = Transcript::load(path)?;
+    transcript
+        .verify(policy)
+        .map_err(|invalid| anyhow::anyhow!("The transcript is invalid at {}", invalid))?;
+
+    let prover = backend.build();
+    let players = match player {
+        Some(player @ 0..=1) => vec![player],
+        Some(player) => anyhow::bail!("There is no player {}; use 0 or 1", player),
+        None => vec![0, 1],
+    };
+    for player in players {
+        let receipts = BoardReceipts::from_transcript(&transcript, player)?;
+        println!("🔐 Folding player {}'s {} receipts...", player, receipts.results.len() + 1);
+        let started = std::time::Instant::now();
+        let receipt = history::prove_history(&receipts, prover.as_ref(), kind, |done, steps| {
+            print!("\r   {}/{} steps", done, steps);
+            let _ = io::stdout().flush();
+        })?;
+        println!();
+
+        let bytes = bincode::serialize(&receipt)?;
+        let out = history::path(path, player);
+        std::fs::write(&out, &bytes)?;
+        println!(
+            "✅ {} receipt, {:.1} KiB, proven in {:.1}s: {}",
+            ReceiptKind::of(&receipt).map_or("fake".to_string(), |kind| kind.to_string()),
+            bytes.len() as f64 / 1024.0,
+            started.elapsed().as_secs_f64(),
+            out.display()
+        );
+    }
+    Ok(())
+}
+
+/// Verify a whole-game proof and print the game it attests to
+fn verify_game(path: &Path, policy: &VerificationPolicy) -> anyhow::Result<()> {
+    let receipt = bincode::deserialize(&std::fs::read(path)?)?;
+    let proven = match history::verify_history(&receipt, policy) {
+        Ok(proven) => proven,
+        Err(e) => {
+            println!("❌ INVALID: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("Session:       {}", proven.session);
+    println!(
+        "Rules:         {}x{} board, {} ships, {} mode",
+        proven.rules.width,
+        proven.rules.height,
+        proven.rules.total_ships(),
+        proven.rules.mode
+    );
+    println!("Policy:        {}", policy.trust_level());
+    println!("Initial board: {}", proven.initial_state);
+    println!("Final board:   {}", proven.state);
+    println!("Turns proven:  {}", proven.turns);
+    println!("Ships afloat:  {}", proven.ships_afloat);
+    println!("Results chain: {}", proven.results);
+    println!("\n✅ VALID: one receipt proves this board's whole game");
+    Ok(())
+}
+
+/// Prove for other players with the configured backend until interrupted
+fn serve_proofs(listen: &str, connections: usize, backend: &ProverConfig) -> anyhow::Result<()> {
+    if let ProverConfig::Remote(_) = backend {
+        anyhow::bail!("A proving daemon cannot hand its work on; use local or dev-fake");
+    }
+    println!("🔐 Proving with the {} backend on {}, {} at a time", backend, listen, connections);
+    println!("⚠️  Players send their secret boards here; only share it with people you trust");
+    if !prover::is_local_address(listen) {
+        println!("⚠️  Requests are not encrypted or authenticated: anyone who can reach");
+        println!("   {} can read the boards and use the prover. Prefer an SSH tunnel.", listen);
+    }
+    prover::serve(listen, Arc::from(backend.build()), connections)
+}
+
+/// Listen for or connect to the opponent; `unix:/path` is a Unix socket and
+/// anything else a TCP address
+fn open_connection(address: &str, port: u16, listen: bool) -> anyhow::Result<Box<dyn Transport>> {
+    // The joining player initiates the encrypted channel
+    match (address.strip_prefix("unix:"), listen) {
+        (Some(path), true) => open_transport(UnixTransport::listen(path)?, false),
+        (Some(path), false) => open_transport(UnixTransport::connect(path)?, true),
+        (None, true) => open_transport(TcpTransport::host(address, port)?, false),
+        (None, false) => open_transport(TcpTransport::connect(address, port)?, true),
+    }
+}
+
+/// Apply the wire settings and, when `BATTLESHIP_SECURE` is set, encrypt the
+/// connection with this player's channel key
+fn open_transport<S: Read + Write + 'static>(
+    transport: StreamTransport<S>,
+    initiator: bool,
+) -> anyhow::Result<Box<dyn Transport>> {
+    let transport = transport.with_env_settings()?;
+    if std::env::var("BATTLESHIP_SECURE").is_err() {
+        return Ok(Box::new(transport));
+    }
+
+    let keys = keys::NoiseKeys::load_or_generate(&keys::key_dir().join("noise.key"))?;
+    println!("🔐 Securing the connection...");
+    println!("   Your key fingerprint: {}", keys.fingerprint());
+    Ok(Box::new(transport.secure(&keys, initiator)?))
+}
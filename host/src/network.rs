@@ -1,30 +1,222 @@
+use crate::framing::{read_frame, write_frame, Codec, FrameError, DEFAULT_MAX_FRAME};
+use crate::keys::{fingerprint, Identity, NoiseKeys, NOISE_PARAMS};
 use crate::network_protocol::GameMessage;
 use anyhow::{Context, Result};
-use std::io::{BufRead, BufReader, Write};
+use ed25519_dalek::VerifyingKey;
+use risc0_zkvm::sha::{Digest, Impl, Sha256};
+use std::io::{BufReader, Read, Write};
 use std::net::{TcpListener, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::mpsc::{channel, Receiver, Sender};
 
-pub struct NetworkConnection {
-    stream: TcpStream,
-    reader: BufReader<TcpStream>,
+/// A connection to the opponent that carries `GameMessage`s in order
+pub trait Transport {
+    /// Send a message
+    fn send(&mut self, message: &GameMessage) -> Result<()>;
+
+    /// Receive a message (blocking)
+    fn receive(&mut self) -> Result<GameMessage>;
+
+    /// Fingerprint of the opponent's channel key, if the channel is authenticated
+    fn peer_fingerprint(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn send(&mut self, message: &GameMessage) -> Result<()> {
+        (**self).send(message)
+    }
+
+    fn receive(&mut self) -> Result<GameMessage> {
+        (**self).receive()
+    }
+
+    fn peer_fingerprint(&self) -> Option<String> {
+        (**self).peer_fingerprint()
+    }
+}
+
+/// Largest Noise message, including the 16 byte authentication tag
+const NOISE_MAX_MESSAGE: usize = 65535;
+const NOISE_TAG: usize = 16;
+
+/// Noise session keys and the authenticated key of the peer
+struct SecureChannel {
+    noise: snow::TransportState,
+    peer_fingerprint: String,
+}
+
+/// Length-prefixed frames over a byte stream
+pub struct StreamTransport<S> {
+    stream: S,
+    reader: BufReader<S>,
+    codec: Codec,
+    max_frame: usize,
+    secure: Option<Box<SecureChannel>>,
 }
 
-impl NetworkConnection {
+pub type TcpTransport = StreamTransport<TcpStream>;
+pub type UnixTransport = StreamTransport<UnixStream>;
+
+impl<S: Read + Write> StreamTransport<S> {
+    fn new(stream: S, reader: S) -> Self {
+        Self {
+            stream,
+            reader: BufReader::new(reader),
+            codec: Codec::default(),
+            max_frame: DEFAULT_MAX_FRAME,
+            secure: None,
+        }
+    }
+
+    /// Run a Noise XX handshake with our static key, then encrypt and
+    /// authenticate every later frame. The connecting player initiates.
+    pub fn secure(mut self, keys: &NoiseKeys, initiator: bool) -> Result<Self> {
+        let builder = snow::Builder::new(NOISE_PARAMS.parse()?).local_private_key(&keys.private);
+        let mut handshake = if initiator {
+            builder.build_initiator()?
+        } else {
+            builder.build_responder()?
+        };
+
+        let mut buf = vec![0u8; NOISE_MAX_MESSAGE];
+        while !handshake.is_handshake_finished() {
+            if handshake.is_my_turn() {
+                let len = handshake.write_message(&[], &mut buf)?;
+                write_frame(&mut self.stream, &buf[..len], NOISE_MAX_MESSAGE)?;
+            } else {
+                let frame = read_frame(&mut self.reader, NOISE_MAX_MESSAGE)?;
+                handshake
+                    .read_message(&frame, &mut buf)
+                    .map_err(|e| anyhow::anyhow!("Secure handshake failed: {}", e))?;
+            }
+        }
+
+        let peer_key = handshake
+            .get_remote_static()
+            .ok_or_else(|| anyhow::anyhow!("Opponent did not send a channel key"))?;
+        self.secure = Some(Box::new(SecureChannel {
+            peer_fingerprint: fingerprint(peer_key),
+            noise: handshake.into_transport_mode()?,
+        }));
+        Ok(self)
+    }
+
+    /// Encode messages with `codec`; both players must use the same one
+    pub fn with_codec(self, codec: Codec) -> Self {
+        Self { codec, ..self }
+    }
+
+    /// Refuse frames longer than `max_frame` bytes in either direction
+    pub fn with_max_frame(self, max_frame: usize) -> Self {
+        Self { max_frame, ..self }
+    }
+
+    /// Apply `BATTLESHIP_CODEC` (`bincode` or `json`) and
+    /// `BATTLESHIP_MAX_FRAME` (bytes) when they are set
+    pub fn with_env_settings(self) -> Result<Self> {
+        let mut transport = self;
+        if let Ok(codec) = std::env::var("BATTLESHIP_CODEC") {
+            transport = transport.with_codec(match codec.to_lowercase().as_str() {
+                "bincode" => Codec::Bincode,
+                "json" => Codec::Json,
+                _ => anyhow::bail!("Unknown BATTLESHIP_CODEC '{}'. Use: bincode or json", codec),
+            });
+        }
+        if let Ok(max_frame) = std::env::var("BATTLESHIP_MAX_FRAME") {
+            let max_frame = max_frame
+                .parse()
+                .with_context(|| format!("Invalid BATTLESHIP_MAX_FRAME '{}'", max_frame))?;
+            transport = transport.with_max_frame(max_frame);
+        }
+        Ok(transport)
+    }
+}
+
+impl<S: Read + Write> Transport for StreamTransport<S> {
+    fn send(&mut self, message: &GameMessage) -> Result<()> {
+        let payload = self.codec.encode(message)?;
+        let Some(secure) = &mut self.secure else {
+            return write_frame(&mut self.stream, &payload, self.max_frame);
+        };
+        if payload.len() > self.max_frame {
+            return Err(FrameError::TooLarge {
+                size: payload.len(),
+                max: self.max_frame,
+            }
+            .into());
+        }
+
+        // Messages are split into Noise-sized chunks; the first carries the total length
+        let mut plain = (payload.len() as u32).to_be_bytes().to_vec();
+        plain.extend_from_slice(&payload);
+
+        let mut buf = vec![0u8; NOISE_MAX_MESSAGE];
+        for chunk in plain.chunks(NOISE_MAX_MESSAGE - NOISE_TAG) {
+            let len = secure.noise.write_message(chunk, &mut buf)?;
+            write_frame(&mut self.stream, &buf[..len], NOISE_MAX_MESSAGE)?;
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<GameMessage> {
+        let Some(secure) = &mut self.secure else {
+            let payload = read_frame(&mut self.reader, self.max_frame)?;
+            return self.codec.decode(&payload);
+        };
+
+        let mut plain = Vec::new();
+        let mut buf = vec![0u8; NOISE_MAX_MESSAGE];
+        loop {
+            let frame = read_frame(&mut self.reader, NOISE_MAX_MESSAGE)?;
+            let len = secure
+                .noise
+                .read_message(&frame, &mut buf)
+                .map_err(|e| anyhow::anyhow!("Message failed authentication: {}", e))?;
+            plain.extend_from_slice(&buf[..len]);
+
+            if plain.len() < 4 {
+                continue;
+            }
+            let size = u32::from_be_bytes(plain[..4].try_into()?) as usize;
+            if size > self.max_frame {
+                return Err(FrameError::TooLarge {
+                    size,
+                    max: self.max_frame,
+                }
+                .into());
+            }
+            if plain.len() >= 4 + size {
+                break;
+            }
+        }
+
+        self.codec.decode(&plain[4..])
+    }
+
+    fn peer_fingerprint(&self) -> Option<String> {
+        self.secure.as_ref().map(|secure| secure.peer_fingerprint.clone())
+    }
+}
+
+impl StreamTransport<TcpStream> {
     /// Host: Create a server and wait for connection
-    pub fn host(port: u16) -> Result<Self> {
-        println!("🌐 Starting server on port {}...", port);
-        let listener = TcpListener::bind(format!("0.0.0.0:{}", port))
+    pub fn host(bind: &str, port: u16) -> Result<Self> {
+        println!("🌐 Starting server on {}:{}...", bind, port);
+        let listener = TcpListener::bind(format!("{}:{}", bind, port))
             .context("Failed to bind to port")?;
-        
+
         println!("⏳ Waiting for opponent to connect...");
         println!("   Share this info with your opponent:");
         println!("   - Your IP address (use 'ip addr' or 'ipconfig')");
         println!("   - Port: {}", port);
-        
+
         let (stream, addr) = listener.accept()?;
         println!("✓ Opponent connected from: {}", addr);
-        
-        let reader = BufReader::new(stream.try_clone()?);
-        Ok(Self { stream, reader })
+
+        let reader = stream.try_clone()?;
+        Ok(Self::new(stream, reader))
     }
 
     /// Client: Connect to a host
@@ -32,26 +224,365 @@ impl NetworkConnection {
         println!("🌐 Connecting to {}:{}...", host, port);
         let stream = TcpStream::connect(format!("{}:{}", host, port))
             .context("Failed to connect to host")?;
-        
+
+        println!("✓ Connected to opponent!");
+
+        let reader = stream.try_clone()?;
+        Ok(Self::new(stream, reader))
+    }
+}
+
+impl StreamTransport<UnixStream> {
+    /// Host: Listen on a Unix domain socket and wait for the opponent
+    pub fn listen(path: &str) -> Result<Self> {
+        println!("🌐 Listening on socket {}...", path);
+        let listener = UnixListener::bind(path).context("Failed to bind to socket")?;
+
+        println!("⏳ Waiting for opponent to connect...");
+        let (stream, _) = listener.accept()?;
+        println!("✓ Opponent connected");
+
+        let reader = stream.try_clone()?;
+        Ok(Self::new(stream, reader))
+    }
+
+    /// Client: Connect to a host listening on a Unix domain socket
+    pub fn connect(path: &str) -> Result<Self> {
+        println!("🌐 Connecting to socket {}...", path);
+        let stream = UnixStream::connect(path).context("Failed to connect to socket")?;
+
         println!("✓ Connected to opponent!");
-        
-        let reader = BufReader::new(stream.try_clone()?);
-        Ok(Self { stream, reader })
+
+        let reader = stream.try_clone()?;
+        Ok(Self::new(stream, reader))
     }
+}
 
-    /// Send a message
-    pub fn send(&mut self, message: &GameMessage) -> Result<()> {
-        let json = serde_json::to_string(message)?;
-        writeln!(self.stream, "{}", json)?;
-        self.stream.flush()?;
+/// Messages handed straight to another thread in this process, for tests and
+/// games against the computer
+pub struct MemoryTransport {
+    tx: Sender<GameMessage>,
+    rx: Receiver<GameMessage>,
+}
+
+impl MemoryTransport {
+    /// Two connected ends
+    pub fn pair() -> (Self, Self) {
+        let (a_tx, b_rx) = channel();
+        let (b_tx, a_rx) = channel();
+        (
+            Self { tx: a_tx, rx: a_rx },
+            Self { tx: b_tx, rx: b_rx },
+        )
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn send(&mut self, message: &GameMessage) -> Result<()> {
+        self.tx
+            .send(message.clone())
+            .map_err(|_| anyhow::anyhow!("Opponent left the game"))
+    }
+
+    fn receive(&mut self) -> Result<GameMessage> {
+        self.rx
+            .recv()
+            .map_err(|_| anyhow::anyhow!("Opponent left the game"))
+    }
+}
+
+/// Signs everything we send with our identity and checks everything we
+/// receive against the identity the opponent first signed with.
+///
+/// Each direction forms a hash chain, so a signature also covers every earlier
+/// message in that direction and nothing can be dropped, reordered or replayed.
+/// The chains start from a seed for their direction, so our own messages
+/// cannot be reflected back at us, and are tied to the session once the
+/// handshake has agreed on it.
+pub struct SignedTransport<T> {
+    inner: T,
+    identity: Identity,
+    peer: Option<VerifyingKey>,
+    /// Every head of each chain so far, starting from its direction's seed
+    sent: Vec<Digest>,
+    received: Vec<Digest>,
+}
+
+impl<T: Transport> SignedTransport<T> {
+    pub fn new(inner: T, identity: Identity, starts_first: bool) -> Self {
+        Self {
+            inner,
+            identity,
+            peer: None,
+            sent: vec![chain_seed(starts_first)],
+            received: vec![chain_seed(!starts_first)],
+        }
+    }
+
+    /// Extend both chains with the session id, so nothing signed for another
+    /// game between the same players verifies in this one. Both players call
+    /// this once each has the other's `Hello`.
+    pub fn bind_session(&mut self, session: &Digest) {
+        for chain in [&mut self.sent, &mut self.received] {
+            let head = *chain.last().expect("chains start with their seed");
+            chain.push(*Impl::hash_bytes(&[head.as_bytes(), session.as_bytes()].concat()));
+        }
+    }
+
+    /// Both chain histories, for saving a game
+    pub fn chains(&self) -> (Vec<Digest>, Vec<Digest>) {
+        (self.sent.clone(), self.received.clone())
+    }
+
+    /// Continue the chains of a resumed game
+    pub fn restore_chains(&mut self, sent: Vec<Digest>, received: Vec<Digest>) {
+        self.sent = sent;
+        self.received = received;
+    }
+
+    pub fn identity(&self) -> &Identity {
+        &self.identity
+    }
+
+    /// The opponent's identity, known once they have sent a message
+    pub fn peer_identity(&self) -> Option<VerifyingKey> {
+        self.peer
+    }
+
+    /// Hash of both message chains, the first player's first. Both players get
+    /// the same value once each has received everything the other sent.
+    pub fn transcript(&self, starts_first: bool) -> Digest {
+        let (sent, received) = (self.sent_head(), self.received_head());
+        let (first, second) = if starts_first {
+            (sent, received)
+        } else {
+            (received, sent)
+        };
+        *Impl::hash_bytes(&[first.as_bytes(), second.as_bytes()].concat())
+    }
+
+    fn sent_head(&self) -> Digest {
+        *self.sent.last().expect("chains start with their seed")
+    }
+
+    fn received_head(&self) -> Digest {
+        *self.received.last().expect("chains start with their seed")
+    }
+}
+
+/// First link of the chain of messages the first player, or the second, sends
+fn chain_seed(from_first_player: bool) -> Digest {
+    let direction: &[u8] = if from_first_player { b"first" } else { b"second" };
+    *Impl::hash_bytes(&[b"battleship signed messages from the ".as_slice(), direction].concat())
+}
+
+/// Next link of a message hash chain
+fn chain(prev: &Digest, message: &GameMessage) -> Result<Digest> {
+    let mut bytes = prev.as_bytes().to_vec();
+    bytes.extend(bincode::serialize(message)?);
+    Ok(*Impl::hash_bytes(&bytes))
+}
+
+impl<T: Transport> Transport for SignedTransport<T> {
+    fn send(&mut self, message: &GameMessage) -> Result<()> {
+        let next = chain(&self.sent_head(), message)?;
+        self.inner.send(&GameMessage::Signed {
+            signer: self.identity.public(),
+            message: Box::new(message.clone()),
+            signature: self.identity.sign(next.as_bytes()),
+        })?;
+        self.sent.push(next);
         Ok(())
     }
 
-    /// Receive a message (blocking)
-    pub fn receive(&mut self) -> Result<GameMessage> {
-        let mut line = String::new();
-        self.reader.read_line(&mut line)?;
-        let message = serde_json::from_str(&line)?;
-        Ok(message)
+    fn receive(&mut self) -> Result<GameMessage> {
+        let GameMessage::Signed {
+            signer,
+            message,
+            signature,
+        } = self.inner.receive()?
+        else {
+            anyhow::bail!("Opponent sent an unsigned message");
+        };
+
+        if signer == self.identity.public() {
+            anyhow::bail!("Opponent's message is signed with our own identity");
+        }
+        if self.peer.is_some_and(|peer| peer != signer) {
+            anyhow::bail!("Opponent's message is signed by a different identity");
+        }
+        let next = chain(&self.received_head(), &message)?;
+        signer
+            .verify_strict(next.as_bytes(), &signature)
+            .map_err(|_| anyhow::anyhow!("Opponent's message signature is invalid"))?;
+
+        self.peer = Some(signer);
+        self.received.push(next);
+        Ok(*message)
+    }
+
+    fn peer_fingerprint(&self) -> Option<String> {
+        self.inner.peer_fingerprint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(a: &mut impl Transport, b: &mut impl Transport) {
+        a.send(&GameMessage::Error {
+            message: "ping".to_string(),
+        })
+        .unwrap();
+        match b.receive().unwrap() {
+            GameMessage::Error { message } => assert_eq!(message, "ping"),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_memory_transport() {
+        let (mut a, mut b) = MemoryTransport::pair();
+        round_trip(&mut a, &mut b);
+        round_trip(&mut b, &mut a);
+
+        drop(a);
+        assert!(b.receive().is_err());
+    }
+
+    #[test]
+    fn test_secure_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let host_keys = NoiseKeys::generate().unwrap();
+        let client_keys = NoiseKeys::generate().unwrap();
+
+        let host_fingerprint = host_keys.fingerprint();
+        let host = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let reader = stream.try_clone().unwrap();
+            TcpTransport::new(stream, reader)
+                .secure(&host_keys, false)
+                .unwrap()
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let reader = stream.try_clone().unwrap();
+        let mut client = TcpTransport::new(stream, reader)
+            .secure(&client_keys, true)
+            .unwrap();
+        let mut host = host.join().unwrap();
+
+        assert_eq!(client.peer_fingerprint(), Some(host_fingerprint));
+        assert_eq!(host.peer_fingerprint(), Some(client_keys.fingerprint()));
+
+        round_trip(&mut host, &mut client);
+        round_trip(&mut client, &mut host);
+
+        // Receipts are far larger than one Noise message
+        let message = "x".repeat(3 * NOISE_MAX_MESSAGE);
+        client
+            .send(&GameMessage::Error {
+                message: message.clone(),
+            })
+            .unwrap();
+        match host.receive().unwrap() {
+            GameMessage::Error { message: received } => assert_eq!(received, message),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_signed_transport() {
+        let (a, b) = MemoryTransport::pair();
+        let mut a = SignedTransport::new(a, Identity::generate(), true);
+        let mut b = SignedTransport::new(b, Identity::generate(), false);
+
+        round_trip(&mut a, &mut b);
+        round_trip(&mut b, &mut a);
+        let session = Digest::from([5u32; 8]);
+        a.bind_session(&session);
+        b.bind_session(&session);
+        round_trip(&mut a, &mut b);
+        assert_eq!(b.peer_identity(), Some(a.identity().public()));
+        assert_eq!(a.transcript(true), b.transcript(false));
+        assert_ne!(a.transcript(true), a.transcript(false));
+
+        // A replayed message no longer matches the chain
+        let ping = GameMessage::Error {
+            message: "ping".to_string(),
+        };
+        let first_link = chain(&chain_seed(true), &ping).unwrap();
+        a.inner
+            .send(&GameMessage::Signed {
+                signer: a.identity().public(),
+                message: Box::new(ping.clone()),
+                signature: a.identity().sign(first_link.as_bytes()),
+            })
+            .unwrap();
+        assert!(b.receive().is_err());
+
+        // Nor does a message signed by someone other than the first signer
+        let (mut a, b) = MemoryTransport::pair();
+        let mut b = SignedTransport::new(b, Identity::generate(), false);
+        let first = Identity::generate();
+        for signer in [&first, &Identity::generate()] {
+            let link = chain(&b.received_head(), &ping).unwrap();
+            a.send(&GameMessage::Signed {
+                signer: signer.public(),
+                message: Box::new(ping.clone()),
+                signature: signer.sign(link.as_bytes()),
+            })
+            .unwrap();
+            let received = b.receive();
+            assert_eq!(received.is_ok(), signer.public() == first.public());
+        }
+        assert_eq!(b.peer_identity(), Some(first.public()));
+
+        // Or one without a signature
+        a.send(&ping).unwrap();
+        assert!(b.receive().is_err());
+
+        // Our own messages echoed back are refused
+        let (mut echo, b) = MemoryTransport::pair();
+        let mut b = SignedTransport::new(b, Identity::generate(), false);
+        b.send(&ping).unwrap();
+        let sent = echo.receive().unwrap();
+        echo.send(&sent).unwrap();
+        let error = b.receive().unwrap_err().to_string();
+        assert!(error.contains("our own identity"), "{}", error);
+
+        // And messages signed for another session do not verify in this one
+        let (a, b) = MemoryTransport::pair();
+        let mut a = SignedTransport::new(a, Identity::generate(), true);
+        let mut b = SignedTransport::new(b, Identity::generate(), false);
+        a.bind_session(&Digest::from([1u32; 8]));
+        b.bind_session(&Digest::from([2u32; 8]));
+        a.send(&ping).unwrap();
+        assert!(b.receive().is_err());
+    }
+
+    #[test]
+    fn test_unix_transport() {
+        let path = std::env::temp_dir().join(format!("battleship-{}.sock", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let listener = {
+            let path = path.clone();
+            std::thread::spawn(move || UnixTransport::listen(&path).unwrap())
+        };
+        let mut client = loop {
+            match UnixTransport::connect(&path) {
+                Ok(client) => break client,
+                Err(_) => std::thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        };
+        let mut host = listener.join().unwrap();
+
+        round_trip(&mut host, &mut client);
+        round_trip(&mut client, &mut host);
+        std::fs::remove_file(&path).unwrap();
     }
-}
\ No newline at end of file
+}
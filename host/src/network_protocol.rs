@@ -1,14 +1,29 @@
-use battleship_core::{HitType, Position, RoundCommit};
+use crate::verification::{ImageIds, ReceiptKind, VerificationPolicy};
+use battleship_core::{GameRules, GameState, HitType, Position, RoundCommit, SalvoCommit};
+use ed25519_dalek::{Signature, VerifyingKey};
 use risc0_zkvm::{Receipt, sha::Digest};
 use serde::{Deserialize, Serialize};
 
 /// Messages sent between players over the network
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GameMessage {
-    /// Initial handshake: send board commitment
+    /// Session negotiation: random nonce each player contributes to the session
+    /// id, the verification policy and guest image IDs both must agree on, the
+    /// proposed rules (the first player's proposal is used) and the receipt
+    /// kinds the player accepts, favourite first
+    Hello {
+        nonce: [u8; 16],
+        policy: VerificationPolicy,
+        image_ids: ImageIds,
+        rules: GameRules,
+        receipt_kinds: Vec<ReceiptKind>,
+    },
+
+    /// Initial handshake: send board commitment with proof the board is valid
     BoardReady {
         commitment: Digest,
         player_name: String,
+        proof: BoardProof,
     },
 
     /// Request to take a shot
@@ -23,15 +38,92 @@ pub enum GameMessage {
         proof: ProofData,
     },
 
-    /// Game over notification
+    /// Salvo mode: fire one shot per surviving ship at once
+    TakeShots {
+        positions: Vec<Position>,
+    },
+
+    /// Salvo mode: response with one ZK proof covering every shot in the salvo
+    SalvoResult {
+        positions: Vec<Position>,
+        hit_types: Vec<HitType>,
+        proof: SalvoProofData,
+    },
+
+    /// Concession from the losing player, proving their whole fleet is sunk
     GameOver {
         winner: String,
+        proof: BoardProof,
+    },
+
+    /// End-of-game reveal of the initial board for the post-game audit
+    RevealBoard {
+        state: GameState,
+    },
+
+    /// Reconnecting to a saved game: how far each side got, so both can
+    /// continue from the last turn they agree on
+    Resume {
+        session: Digest,
+        /// Opponent turns whose proofs we verified
+        verified: u32,
+        /// Turns we proved for the opponent's shots
+        proven: u32,
+        sent: ChainHead,
+        received: ChainHead,
+    },
+
+    /// Both players' signatures over the final `GameResult`
+    ResultSignature {
+        signature: Signature,
     },
 
     /// Error message
     Error {
         message: String,
     },
+
+    /// Envelope every other message travels in, signed by the sender's
+    /// identity over the hash chain of everything they sent before
+    Signed {
+        signer: VerifyingKey,
+        message: Box<GameMessage>,
+        signature: Signature,
+    },
+}
+
+/// Length and latest hash of one direction's signed message chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainHead {
+    pub len: u32,
+    pub head: Digest,
+}
+
+impl ChainHead {
+    pub fn of(chain: &[Digest]) -> Self {
+        Self {
+            len: chain.len() as u32,
+            head: *chain.last().expect("chains start with the zero digest"),
+        }
+    }
+}
+
+/// Serializable receipt about a committed board: either the init proof that it
+/// passes `GameState::check`, or the fleet-sunk proof that ends the game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardProof {
+    pub receipt_bytes: Vec<u8>,
+}
+
+impl BoardProof {
+    pub fn from_receipt(receipt: &Receipt) -> anyhow::Result<Self> {
+        let receipt_bytes = bincode::serialize(receipt)?;
+        Ok(Self { receipt_bytes })
+    }
+
+    pub fn to_receipt(&self) -> anyhow::Result<Receipt> {
+        Ok(bincode::deserialize(&self.receipt_bytes)?)
+    }
 }
 
 /// Serializable proof data
@@ -53,4 +145,103 @@ impl ProofData {
     pub fn to_receipt(&self) -> anyhow::Result<Receipt> {
         Ok(bincode::deserialize(&self.receipt_bytes)?)
     }
-}
\ No newline at end of file
+}
+/// Serializable salvo proof data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SalvoProofData {
+    pub receipt_bytes: Vec<u8>,
+    pub commit: SalvoCommit,
+}
+
+impl SalvoProofData {
+    pub fn from_receipt(receipt: Receipt, commit: SalvoCommit) -> anyhow::Result<Self> {
+        let receipt_bytes = bincode::serialize(&receipt)?;
+        Ok(Self {
+            receipt_bytes,
+            commit,
+        })
+    }
+
+    pub fn to_receipt(&self) -> anyhow::Result<Receipt> {
+        Ok(bincode::deserialize(&self.receipt_bytes)?)
+    }
+}
+
+/// Outcome of a finished game, listing the first player before the second
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameResult {
+    pub session: Digest,
+    pub players: [VerifyingKey; 2],
+    pub names: [String; 2],
+    /// Initial board commitments
+    pub commitments: [Digest; 2],
+    pub winner: VerifyingKey,
+    /// Hash of every signed message either player sent, see `SignedTransport::transcript`
+    pub transcript: Digest,
+}
+
+impl GameResult {
+    pub fn signing_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+}
+
+/// A `GameResult` with both players' signatures, verifiable offline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedGameResult {
+    pub result: GameResult,
+    pub signatures: [Signature; 2],
+}
+
+impl SignedGameResult {
+    pub fn verify(&self) -> anyhow::Result<()> {
+        let [first, second] = &self.result.players;
+        if first == second {
+            anyhow::bail!("game result has the same player on both sides");
+        }
+        let bytes = self.result.signing_bytes()?;
+        for (player, signature) in self.result.players.iter().zip(&self.signatures) {
+            player
+                .verify_strict(&bytes, signature)
+                .map_err(|_| anyhow::anyhow!("game result signature of {:?} is invalid", player))?;
+        }
+        if !self.result.players.contains(&self.result.winner) {
+            anyhow::bail!("game result names a winner who did not play");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::Identity;
+
+    fn signed(players: [&Identity; 2]) -> SignedGameResult {
+        let result = GameResult {
+            session: Digest::from([1u32; 8]),
+            players: players.map(Identity::public),
+            names: ["Alice".to_string(), "Bob".to_string()],
+            commitments: [Digest::from([2u32; 8]), Digest::from([3u32; 8])],
+            winner: players[0].public(),
+            transcript: Digest::from([4u32; 8]),
+        };
+        let bytes = result.signing_bytes().unwrap();
+        let signatures = players.map(|player| player.sign(&bytes));
+        SignedGameResult { result, signatures }
+    }
+
+    #[test]
+    fn test_signed_result() {
+        let (alice, bob) = (Identity::generate(), Identity::generate());
+        signed([&alice, &bob]).verify().unwrap();
+
+        // One key cannot sign both sides of a game
+        let error = signed([&alice, &alice]).verify().unwrap_err().to_string();
+        assert_eq!(error, "game result has the same player on both sides");
+
+        let mut swapped = signed([&alice, &bob]);
+        swapped.signatures.swap(0, 1);
+        assert!(swapped.verify().is_err());
+    }
+}
@@ -0,0 +1,516 @@
+use crate::framing::{read_frame, write_frame, DEFAULT_MAX_FRAME};
+use crate::verification::{ReceiptKind, VerificationPolicy};
+use anyhow::Context;
+use battleship_core::history::HistoryInput;
+use battleship_core::{BoardCommit, BoardInput, RoundCommit, RoundInput, SalvoCommit, SalvoInput};
+use battleship_guests::{
+    FLEET_SUNK_ELF, FLEET_SUNK_ID, HISTORY_ELF, HISTORY_ID, INIT_ELF, INIT_ID, ROUND_ELF, ROUND_ID,
+    SALVO_ELF, SALVO_ID,
+};
+use risc0_zkvm::{
+    default_prover, sha::Digest, ExecutorEnv, FakeReceipt, InnerReceipt, ProverOpts, Receipt,
+    ReceiptClaim,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+/// Port the proving daemon listens on unless told otherwise
+pub const DEFAULT_PROVER_PORT: u16 = 7879;
+/// Requests the proving daemon works on at once unless told otherwise
+pub const DEFAULT_PROVER_CONNECTIONS: usize = 4;
+/// How long a client may take to send its request before the daemon hangs up
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// One guest run to prove, with its input
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ProofRequest {
+    /// A fresh board is a valid fleet, with the init guest
+    Board(BoardInput),
+    /// The result of one shot, with the round guest
+    Round(RoundInput),
+    /// The results of a salvo, with the salvo guest
+    Salvo(SalvoInput),
+    /// Every ship is sunk, with the fleet-sunk guest
+    FleetSunk(BoardInput),
+    /// One more step of a board's whole-game history, with the history guest;
+    /// `assumptions` are the receipts the step's journals come from
+    History {
+        input: Box<HistoryInput>,
+        assumptions: Vec<Receipt>,
+    },
+}
+
+impl ProofRequest {
+    pub fn image_id(&self) -> Digest {
+        match self {
+            Self::Board(_) => INIT_ID.into(),
+            Self::Round(_) => ROUND_ID.into(),
+            Self::Salvo(_) => SALVO_ID.into(),
+            Self::FleetSunk(_) => FLEET_SUNK_ID.into(),
+            Self::History { .. } => HISTORY_ID.into(),
+        }
+    }
+
+    fn elf(&self) -> &'static [u8] {
+        match self {
+            Self::Board(_) => INIT_ELF,
+            Self::Round(_) => ROUND_ELF,
+            Self::Salvo(_) => SALVO_ELF,
+            Self::FleetSunk(_) => FLEET_SUNK_ELF,
+            Self::History { .. } => HISTORY_ELF,
+        }
+    }
+
+    fn env(&self) -> anyhow::Result<ExecutorEnv<'static>> {
+        let mut builder = ExecutorEnv::builder();
+        match self {
+            Self::Board(input) | Self::FleetSunk(input) => builder.write(input)?,
+            Self::Round(input) => builder.write(input)?,
+            Self::Salvo(input) => builder.write(input)?,
+            Self::History { input, assumptions } => {
+                for receipt in assumptions {
+                    builder.add_assumption(receipt.clone());
+                }
+                builder.write(input)?
+            }
+        };
+        builder.build()
+    }
+
+    /// The journal the guest would commit, worked out on the host with the
+    /// same core logic; fails wherever the guest would panic
+    pub fn journal(&self) -> anyhow::Result<Vec<u8>> {
+        let words = match self {
+            Self::Board(BoardInput { session, state }) => {
+                state.check()?;
+                let commit = BoardCommit {
+                    session: *session,
+                    rules: state.rules.clone(),
+                    state: state.commit(),
+                };
+                risc0_zkvm::serde::to_vec(&commit)?
+            }
+            Self::Round(RoundInput { session, turn, state, shot }) => {
+                let mut state = state.clone();
+                let old_state = state.commit();
+                let hit = state.apply_shot(*shot)?;
+                let commit = RoundCommit {
+                    session: *session,
+                    turn: *turn,
+                    old_state,
+                    new_state: state.commit(),
+                    shot: *shot,
+                    hit,
+                };
+                risc0_zkvm::serde::to_vec(&commit)?
+            }
+            Self::Salvo(SalvoInput { session, turn, state, shots }) => {
+                let mut state = state.clone();
+                let old_state = state.commit();
+                let hits = state.apply_salvo(shots)?;
+                let commit = SalvoCommit {
+                    session: *session,
+                    turn: *turn,
+                    old_state,
+                    new_state: state.commit(),
+                    shots: shots.clone(),
+                    hits,
+                    ships_afloat: state.ships_afloat(),
+                };
+                risc0_zkvm::serde::to_vec(&commit)?
+            }
+            Self::FleetSunk(BoardInput { session, state }) => {
+                if !state.all_sunk() {
+                    anyhow::bail!("at least one ship is still afloat");
+                }
+                let commit = BoardCommit {
+                    session: *session,
+                    rules: state.rules.clone(),
+                    state: state.commit(),
+                };
+                risc0_zkvm::serde::to_vec(&commit)?
+            }
+            Self::History { input, .. } => risc0_zkvm::serde::to_vec(&input.apply()?)?,
+        };
+        Ok(bytemuck::cast_slice(&words).to_vec())
+    }
+}
+
+/// Turns guest inputs into receipts of the agreed kind; where the proving
+/// happens is up to the backend
+pub trait ShotProver: Send + Sync {
+    fn prove(&self, request: &ProofRequest, kind: ReceiptKind) -> anyhow::Result<Receipt>;
+}
+
+/// Proves in this process with the RISC Zero prover, which honours `RISC0_DEV_MODE`
+pub struct LocalProver;
+
+impl ShotProver for LocalProver {
+    fn prove(&self, request: &ProofRequest, kind: ReceiptKind) -> anyhow::Result<Receipt> {
+        let opts = match kind {
+            ReceiptKind::Composite => ProverOpts::composite(),
+            ReceiptKind::Succinct => ProverOpts::succinct(),
+            ReceiptKind::Groth16 => ProverOpts::groth16(),
+        };
+        let prove_info = default_prover().prove_with_opts(request.env()?, request.elf(), &opts)?;
+        Ok(prove_info.receipt)
+    }
+}
+
+/// Makes fake receipts for the journal the guest would commit, without
+/// running the zkVM at all; only the dev-fake policy accepts them. Fake
+/// receipts have no kind, so any kind asked for is the same to it.
+pub struct FakeProver;
+
+impl ShotProver for FakeProver {
+    fn prove(&self, request: &ProofRequest, _kind: ReceiptKind) -> anyhow::Result<Receipt> {
+        let journal = request.journal()?;
+        let claim = ReceiptClaim::ok(request.image_id(), journal.clone());
+        Ok(Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal))
+    }
+}
+
+/// Sends each request to a proving daemon started with `battleship prover`.
+///
+/// The daemon sees our secret board, so it must be a machine we trust.
+pub struct RemoteProver {
+    address: String,
+}
+
+impl RemoteProver {
+    /// `address` is `host:port` or `unix:/path/to/socket`
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+        }
+    }
+}
+
+impl ShotProver for RemoteProver {
+    fn prove(&self, request: &ProofRequest, kind: ReceiptKind) -> anyhow::Result<Receipt> {
+        let mut stream = connect(&self.address)
+            .with_context(|| format!("Failed to reach the prover at {}", self.address))?;
+        write_frame(&mut stream, &bincode::serialize(&(request, kind))?, DEFAULT_MAX_FRAME)?;
+
+        let reply: Result<Receipt, String> =
+            bincode::deserialize(&read_frame(&mut stream, DEFAULT_MAX_FRAME)?)?;
+        reply.map_err(|e| anyhow::anyhow!("The prover at {} failed: {}", self.address, e))
+    }
+}
+
+/// Which prover backend to use
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProverConfig {
+    Local,
+    DevFake,
+    /// A proving daemon at `host:port` or `unix:/path/to/socket`
+    Remote(String),
+}
+
+impl ProverConfig {
+    /// `BATTLESHIP_PROVER` in the form `--prover` takes, local when unset
+    pub fn from_env() -> anyhow::Result<Self> {
+        match std::env::var("BATTLESHIP_PROVER") {
+            Ok(spec) => spec.parse(),
+            Err(_) => Ok(Self::Local),
+        }
+    }
+
+    /// Fake receipts are only worth making when the policy accepts them
+    pub fn check_policy(&self, policy: &VerificationPolicy) -> anyhow::Result<()> {
+        if *self == Self::DevFake && *policy != VerificationPolicy::DevFake {
+            anyhow::bail!("The dev-fake prover needs the dev-fake verification policy");
+        }
+        Ok(())
+    }
+
+    /// Groth16 receipts need the STARK-to-SNARK prover, which only runs on
+    /// x86_64; a daemon or fake receipts can stand in elsewhere
+    pub fn check_kinds(&self, kinds: &[ReceiptKind]) -> anyhow::Result<()> {
+        let local = *self == Self::Local && !cfg!(target_arch = "x86_64");
+        if local && kinds.contains(&ReceiptKind::Groth16) {
+            anyhow::bail!("Groth16 receipts cannot be proven here; use a proving daemon");
+        }
+        Ok(())
+    }
+
+    pub fn build(&self) -> Box<dyn ShotProver> {
+        match self {
+            Self::Local => Box::new(LocalProver),
+            Self::DevFake => Box::new(FakeProver),
+            Self::Remote(address) => Box::new(RemoteProver::new(address.clone())),
+        }
+    }
+}
+
+impl Display for ProverConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Local => write!(f, "local"),
+            Self::DevFake => write!(f, "dev-fake"),
+            Self::Remote(address) => write!(f, "remote:{}", address),
+        }
+    }
+}
+
+/// `local`, `dev-fake` or `remote:<address>`, as accepted on the command line
+impl FromStr for ProverConfig {
+    type Err = anyhow::Error;
+
+    fn from_str(spec: &str) -> anyhow::Result<Self> {
+        match spec {
+            "local" => Ok(Self::Local),
+            "dev-fake" => Ok(Self::DevFake),
+            _ => match spec.strip_prefix("remote:") {
+                Some(address) if !address.is_empty() => Ok(Self::Remote(address.to_string())),
+                _ => anyhow::bail!(
+                    "unknown prover '{}'; use local, dev-fake or remote:<address>",
+                    spec
+                ),
+            },
+        }
+    }
+}
+
+trait Stream: Read + Write + Send {}
+
+impl<S: Read + Write + Send> Stream for S {}
+
+fn connect(address: &str) -> anyhow::Result<Box<dyn Stream>> {
+    Ok(match address.strip_prefix("unix:") {
+        Some(path) => Box::new(UnixStream::connect(path)?),
+        None => Box::new(TcpStream::connect(address)?),
+    })
+}
+
+/// Prove requests for any number of players, one connection per request,
+/// each on its own thread with at most `connections` at once; later
+/// connections wait to be accepted. A connection that cannot be accepted is
+/// logged and skipped, so the daemon runs until it is stopped.
+///
+/// Requests are neither encrypted nor authenticated, so anyone who can reach
+/// `address` can read the boards sent to it and prove on it.
+pub fn serve(
+    address: &str,
+    prover: Arc<dyn ShotProver>,
+    connections: usize,
+) -> anyhow::Result<()> {
+    let mut accept: Box<dyn FnMut() -> std::io::Result<Box<dyn Stream>>> =
+        match address.strip_prefix("unix:") {
+            Some(path) => {
+                let socket = SocketListener::bind(Path::new(path))?;
+                Box::new(move || {
+                    let stream = socket.listener.accept()?.0;
+                    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+                    Ok(Box::new(stream) as Box<dyn Stream>)
+                })
+            }
+            None => {
+                let listener = TcpListener::bind(address).context("Failed to bind to port")?;
+                Box::new(move || {
+                    let stream = listener.accept()?.0;
+                    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+                    Ok(Box::new(stream) as Box<dyn Stream>)
+                })
+            }
+        };
+
+    // Each connection holds a slot until it is answered
+    let (release, slots) = mpsc::sync_channel(connections.max(1));
+    for _ in 0..connections.max(1) {
+        release.send(())?;
+    }
+    loop {
+        slots.recv()?;
+        let mut stream = match accept() {
+            Ok(stream) => stream,
+            Err(e) => {
+                // Out of file descriptors or a client that hung up already;
+                // back off a little so a persistent failure does not spin
+                eprintln!("⚠️  Failed to accept a connection: {}", e);
+                release.send(())?;
+                std::thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+        };
+        let (prover, release) = (prover.clone(), release.clone());
+        std::thread::spawn(move || {
+            if let Err(e) = answer(&mut stream, prover.as_ref()) {
+                eprintln!("⚠️  Proof request failed: {:#}", e);
+            }
+            let _ = release.send(());
+        });
+    }
+}
+
+/// The daemon's Unix socket, whose file is removed when it is dropped
+struct SocketListener {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl SocketListener {
+    /// Bind at `path`, replacing a socket file left behind by a daemon that
+    /// was killed, but not one that still answers or a file that is not a socket
+    fn bind(path: &Path) -> anyhow::Result<Self> {
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                anyhow::bail!("{} exists and is not a socket", path.display());
+            }
+            if UnixStream::connect(path).is_ok() {
+                anyhow::bail!("Another prover is already listening on {}", path.display());
+            }
+            std::fs::remove_file(path)
+                .with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+        }
+        Ok(Self {
+            listener: UnixListener::bind(path).context("Failed to bind to socket")?,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for SocketListener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Whether `address` only takes connections from this machine
+pub fn is_local_address(address: &str) -> bool {
+    if address.starts_with("unix:") {
+        return true;
+    }
+    match address.parse::<std::net::SocketAddr>() {
+        Ok(address) => address.ip().is_loopback(),
+        Err(_) => address.starts_with("localhost:"),
+    }
+}
+
+/// Read one request, prove it and send back the receipt or why it failed
+fn answer(stream: &mut dyn Stream, prover: &dyn ShotProver) -> anyhow::Result<()> {
+    let mut stream = stream;
+    let (request, kind): (ProofRequest, ReceiptKind) =
+        bincode::deserialize(&read_frame(&mut stream, DEFAULT_MAX_FRAME)?)?;
+
+    let started = std::time::Instant::now();
+    let reply = prover.prove(&request, kind).map_err(|e| format!("{:#}", e));
+    println!(
+        "{} {} {:?} proof in {:.1}s",
+        if reply.is_ok() { "✓" } else { "✗" },
+        kind,
+        request.image_id(),
+        started.elapsed().as_secs_f64()
+    );
+    write_frame(&mut stream, &bincode::serialize(&reply)?, DEFAULT_MAX_FRAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use battleship_core::{GameRules, GameState, Position};
+
+    fn round(state: &GameState) -> ProofRequest {
+        ProofRequest::Round(RoundInput {
+            session: Digest::from([3u32; 8]),
+            turn: 0,
+            state: state.clone(),
+            shot: Position::new(0, 0),
+        })
+    }
+
+    #[test]
+    fn test_fake_prover() {
+        let state = GameState::random(&GameRules::classic(), &mut rand::thread_rng()).unwrap();
+        let request = round(&state);
+        let receipt = FakeProver.prove(&request, ReceiptKind::Succinct).unwrap();
+
+        // The journal decodes like the round guest's would
+        let commit: RoundCommit = receipt.journal.decode().unwrap();
+        assert_eq!(commit.old_state, state.commit());
+        VerificationPolicy::DevFake.verify(&receipt, ROUND_ID.into()).unwrap();
+        assert!(VerificationPolicy::Strict.verify(&receipt, ROUND_ID.into()).is_err());
+
+        // What the guest would refuse to prove is refused
+        let afloat = ProofRequest::FleetSunk(BoardInput {
+            session: Digest::default(),
+            state,
+        });
+        assert!(FakeProver.prove(&afloat, ReceiptKind::Succinct).is_err());
+    }
+
+    #[test]
+    fn test_remote_prover() {
+        let dir = std::env::temp_dir().join(format!("battleship-prover-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("prover.sock");
+        let _ = std::fs::remove_file(&socket);
+
+        // A socket left behind by a killed daemon is replaced, a live one is not
+        drop(UnixListener::bind(&socket).unwrap());
+        let address = format!("unix:{}", socket.display());
+        let server = address.clone();
+        std::thread::spawn(move || serve(&server, Arc::new(FakeProver), 1));
+        while UnixStream::connect(&socket).is_err() {
+            std::thread::yield_now();
+        }
+
+        let state = GameState::random(&GameRules::classic(), &mut rand::thread_rng()).unwrap();
+        let remote = RemoteProver::new(address.clone());
+        let receipt = remote.prove(&round(&state), ReceiptKind::Groth16).unwrap();
+        assert_eq!(receipt.journal.bytes, round(&state).journal().unwrap());
+
+        // The daemon's failures come back as errors, not a hang
+        let shot_off_board = ProofRequest::Round(RoundInput {
+            shot: Position::new(50, 50),
+            ..match round(&state) {
+                ProofRequest::Round(input) => input,
+                _ => unreachable!(),
+            }
+        });
+        let error = remote.prove(&shot_off_board, ReceiptKind::Composite).unwrap_err().to_string();
+        assert!(error.contains("outside the board"), "{}", error);
+
+        // With one slot, a client that never sends holds up the next until it is dropped
+        let idle = UnixStream::connect(&socket).unwrap();
+        let waiting =
+            std::thread::spawn(move || remote.prove(&round(&state), ReceiptKind::Succinct));
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!waiting.is_finished());
+        drop(idle);
+        waiting.join().unwrap().unwrap();
+
+        let error = serve(&address, Arc::new(FakeProver), 1).unwrap_err().to_string();
+        assert!(error.contains("already listening"), "{}", error);
+        let _ = std::fs::remove_file(&socket);
+        let listener = SocketListener::bind(&socket).unwrap();
+        assert!(socket.exists());
+        drop(listener);
+        assert!(!socket.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_prover_config() {
+        assert_eq!("dev-fake".parse::<ProverConfig>().unwrap(), ProverConfig::DevFake);
+        let remote: ProverConfig = "remote:unix:/tmp/prover.sock".parse().unwrap();
+        assert_eq!(remote, ProverConfig::Remote("unix:/tmp/prover.sock".to_string()));
+        assert_eq!(remote.to_string(), "remote:unix:/tmp/prover.sock");
+        assert!("remote:".parse::<ProverConfig>().is_err());
+
+        assert!(is_local_address("127.0.0.1:7879"));
+        assert!(is_local_address("[::1]:7879"));
+        assert!(is_local_address("unix:/tmp/prover.sock"));
+        assert!(!is_local_address("0.0.0.0:7879"));
+        assert!(!is_local_address("192.168.1.10:7879"));
+    }
+}
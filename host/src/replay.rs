@@ -0,0 +1,242 @@
+use crate::board_display::BoardDisplay;
+use crate::network_protocol::GameMessage;
+use crate::transcript::{InvalidStep, Transcript};
+use crate::verification::VerificationPolicy;
+use battleship_core::{GameRules, GameState, HitType, Position};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// One proven turn: the results a player proved for the shots at their board
+struct Turn {
+    board: usize,
+    shots: Vec<(Position, HitType)>,
+}
+
+/// A recorded game, stepped through turn by turn from its proven results
+pub struct Replay {
+    rules: GameRules,
+    names: [String; 2],
+    turns: Vec<Turn>,
+    /// Initial boards, when the game ended with the reveal
+    revealed: [Option<GameState>; 2],
+    winner: Option<String>,
+    /// The step the recording stops before, because it does not verify
+    invalid: Option<InvalidStep>,
+}
+
+impl Replay {
+    pub fn load(path: &Path, policy: &VerificationPolicy) -> anyhow::Result<Self> {
+        Self::from_transcript(&Transcript::load(path)?, policy)
+    }
+
+    /// Replay the steps of `transcript` that verify under `policy`, up to the
+    /// first one that does not; a header the policy refuses replays nothing
+    pub fn from_transcript(
+        transcript: &Transcript,
+        policy: &VerificationPolicy,
+    ) -> anyhow::Result<Self> {
+        let (entries, invalid) = match transcript.verify(policy) {
+            Ok(_) => (&transcript.entries[..], None),
+            Err(invalid) if invalid.step == 0 => {
+                anyhow::bail!("The transcript does not verify: {}", invalid)
+            }
+            Err(invalid) => {
+                let valid = (invalid.step - 1).min(transcript.entries.len());
+                (&transcript.entries[..valid], Some(invalid))
+            }
+        };
+
+        let mut replay = Self {
+            rules: transcript.header.rules.clone(),
+            names: ["Player 1".to_string(), "Player 2".to_string()],
+            turns: Vec::new(),
+            revealed: [None, None],
+            winner: None,
+            invalid,
+        };
+
+        // Verified steps come from player 0 or 1 only
+        for entry in entries {
+            let board = entry.player;
+            match &entry.message {
+                GameMessage::BoardReady { player_name, .. } => {
+                    replay.names[board] = player_name.clone();
+                }
+                GameMessage::ShotResult { position, hit_type, .. } => replay.turns.push(Turn {
+                    board,
+                    shots: vec![(*position, hit_type.clone())],
+                }),
+                GameMessage::SalvoResult { positions, hit_types, .. } => replay.turns.push(Turn {
+                    board,
+                    shots: positions.iter().copied().zip(hit_types.iter().cloned()).collect(),
+                }),
+                GameMessage::GameOver { winner, .. } => replay.winner = Some(winner.clone()),
+                GameMessage::RevealBoard { state } => replay.revealed[board] = Some(state.clone()),
+                _ => {}
+            }
+        }
+        Ok(replay)
+    }
+
+    /// Step through the game interactively until the viewer quits
+    pub fn run(&self) -> anyhow::Result<()> {
+        let last = self.turns.len();
+        let mut turn = 0;
+
+        loop {
+            self.show(turn);
+
+            print!("\n[Enter/n]ext  [b]ack  turn number to jump  [q]uit: ");
+            io::stdout().flush()?;
+
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input)? == 0 {
+                return Ok(());
+            }
+
+            match input.trim() {
+                "" | "n" => turn = (turn + 1).min(last),
+                "b" => turn = turn.saturating_sub(1),
+                "q" => return Ok(()),
+                number => match number.parse::<usize>() {
+                    Ok(n) if n <= last => turn = n,
+                    _ => println!("No turn {}; the game has turns 0 to {}", number, last),
+                },
+            }
+        }
+    }
+
+    /// Both boards after the first `turn` turns
+    fn boards_at(&self, turn: usize) -> [BoardDisplay; 2] {
+        let mut boards = [
+            BoardDisplay::new(self.rules.clone()),
+            BoardDisplay::new(self.rules.clone()),
+        ];
+        for played in &self.turns[..turn] {
+            for (pos, hit) in &played.shots {
+                boards[played.board].record_shot(*pos, hit.clone());
+            }
+        }
+        boards
+    }
+
+    fn show(&self, turn: usize) {
+        println!("\n═══════════════════════════════════════════════");
+        match turn.checked_sub(1).map(|i| &self.turns[i]) {
+            None => println!("  Turn 0 of {}: the boards before the first shot", self.turns.len()),
+            Some(played) => {
+                let shooter = &self.names[1 - played.board];
+                let shots: Vec<String> = played
+                    .shots
+                    .iter()
+                    .map(|(pos, hit)| format!("{} {}", pos, describe(hit)))
+                    .collect();
+                println!(
+                    "  Turn {} of {}: {} fired at {}",
+                    turn,
+                    self.turns.len(),
+                    shooter,
+                    shots.join(", ")
+                );
+            }
+        }
+        println!("═══════════════════════════════════════════════");
+
+        for (board, display) in self.boards_at(turn).iter().enumerate() {
+            let title = format!("{}'S BOARD", self.names[board].to_uppercase());
+            match &self.revealed[board] {
+                Some(state) => display.display_fleet(&title, state),
+                None => display.display_shots(&title),
+            }
+        }
+
+        if turn == self.turns.len() {
+            match &self.winner {
+                Some(winner) => println!("\n🏆 {} won", winner),
+                None => println!("\n⏸  The recording ends here; the game did not finish"),
+            }
+            if self.revealed.iter().any(Option::is_none) {
+                println!("   Not every board was revealed, so some fleets stay hidden");
+            }
+            if let Some(invalid) = &self.invalid {
+                println!("❌ The recording stops before {}", invalid);
+            }
+        }
+    }
+}
+
+fn describe(hit: &HitType) -> String {
+    match hit {
+        HitType::Miss => "miss".to_string(),
+        HitType::Hit => "hit".to_string(),
+        HitType::Sunk(class) => format!("sank the {}", class),
+        HitType::Repeat => "repeat".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network_protocol::{BoardProof, ProofData};
+    use crate::prover::{FakeProver, ProofRequest, ShotProver};
+    use crate::transcript::{Entry, TranscriptHeader};
+    use crate::verification::{ImageIds, ReceiptKind};
+    use battleship_core::{BoardInput, RoundCommit, RoundInput};
+    use risc0_zkvm::sha::Digest;
+
+    #[test]
+    fn test_replay_verified_steps() {
+        let session = Digest::from([1u32; 8]);
+        let rules = GameRules::classic();
+        let prove = |request| FakeProver.prove(&request, ReceiptKind::Succinct).unwrap();
+
+        let mut entries = Vec::new();
+        let mut states = Vec::new();
+        for (player, name) in ["Alice", "Bob"].into_iter().enumerate() {
+            let state = GameState::random(&rules, &mut rand::thread_rng()).unwrap();
+            let receipt = prove(ProofRequest::Board(BoardInput { session, state: state.clone() }));
+            let message = GameMessage::BoardReady {
+                commitment: state.commit(),
+                player_name: name.to_string(),
+                proof: BoardProof::from_receipt(&receipt).unwrap(),
+            };
+            entries.push(Entry { player, message });
+            states.push(state);
+        }
+
+        // Alice fires once and Bob proves the result
+        let shot = Position::new(0, 0);
+        entries.push(Entry { player: 0, message: GameMessage::TakeShot { position: shot } });
+        let input = RoundInput { session, turn: 0, state: states[1].clone(), shot };
+        let receipt = prove(ProofRequest::Round(input));
+        let commit: RoundCommit = receipt.journal.decode().unwrap();
+        let message = GameMessage::ShotResult {
+            position: shot,
+            hit_type: commit.hit.clone(),
+            proof: ProofData::from_receipt(receipt, commit).unwrap(),
+        };
+        entries.push(Entry { player: 1, message });
+
+        let header = TranscriptHeader { session, rules, image_ids: [ImageIds::local(); 2] };
+        let mut transcript = Transcript { header, entries, outcome: None };
+        let replay = Replay::from_transcript(&transcript, &VerificationPolicy::DevFake).unwrap();
+        assert_eq!(replay.names, ["Alice", "Bob"]);
+        assert!(replay.invalid.is_none());
+        let boards = replay.boards_at(1);
+        assert!(boards[1].already_shot(shot));
+        assert!(!boards[0].already_shot(shot));
+
+        // A step from a third player ends the replay before it
+        let stray = GameMessage::TakeShot { position: Position::new(1, 1) };
+        transcript.entries.push(Entry { player: 2, message: stray });
+        let replay = Replay::from_transcript(&transcript, &VerificationPolicy::DevFake).unwrap();
+        assert_eq!(replay.turns.len(), 1);
+        assert_eq!(replay.invalid.map(|invalid| invalid.step), Some(5));
+
+        // Fake receipts do not verify under the strict policy, so nothing is shown
+        let replay = Replay::from_transcript(&transcript, &VerificationPolicy::Strict).unwrap();
+        assert_eq!(replay.names, ["Player 1", "Player 2"]);
+        assert!(replay.turns.is_empty());
+        assert_eq!(replay.invalid.map(|invalid| invalid.step), Some(1));
+    }
+}
@@ -0,0 +1,97 @@
+use battleship_core::{FleetEntry, GameMode, GameRules, ShipClass};
+use std::io::{self, Write};
+
+/// Ask the host which board size, fleet and mode to play with
+pub fn prompt_rules() -> anyhow::Result<GameRules> {
+    println!("\n╔═══════════════════════════════════════════════╗");
+    println!("║                GAME RULES                     ║");
+    println!("╚═══════════════════════════════════════════════╝\n");
+
+    loop {
+        let mut rules = GameRules::classic();
+
+        let size = prompt("Board size WxH (Enter for classic 10x10): ")?;
+        if !size.is_empty() {
+            match parse_board_size(&size) {
+                Ok((width, height)) => {
+                    rules.width = width;
+                    rules.height = height;
+                }
+                Err(e) => {
+                    println!("✗ {}", e);
+                    continue;
+                }
+            }
+        }
+
+        println!("Fleet as name:symbol:length:count, comma separated");
+        println!("  e.g. Carrier:A:5:1,Destroyer:D:2:3");
+        let fleet = prompt("Fleet (Enter for the classic five ships): ")?;
+        if !fleet.is_empty() {
+            match parse_fleet(&fleet) {
+                Ok(fleet) => rules.fleet = fleet,
+                Err(e) => {
+                    println!("✗ {}", e);
+                    continue;
+                }
+            }
+        }
+
+        println!("Mode: classic (one shot, again on a hit) or salvo (one shot per ship still afloat)");
+        match prompt("Mode (Enter for classic): ")?.to_lowercase().as_str() {
+            "" | "classic" => rules.mode = GameMode::Classic,
+            "salvo" => rules.mode = GameMode::Salvo,
+            other => {
+                println!("✗ Unknown mode '{}'. Use: classic or salvo", other);
+                continue;
+            }
+        }
+
+        if !rules.check() {
+            println!("✗ That fleet does not fit those rules (ships up to 8 long, unique names and symbols, enough room)");
+            continue;
+        }
+        return Ok(rules);
+    }
+}
+
+/// Parse `WxH`, e.g. `12x8`
+pub fn parse_board_size(spec: &str) -> anyhow::Result<(u32, u32)> {
+    let (width, height) = spec
+        .trim()
+        .split_once(['x', 'X'])
+        .ok_or_else(|| anyhow::anyhow!("Invalid board size '{}'. Use: WxH", spec))?;
+    Ok((width.trim().parse()?, height.trim().parse()?))
+}
+
+/// Parse `name:symbol:length:count` entries separated by commas
+pub fn parse_fleet(spec: &str) -> anyhow::Result<Vec<FleetEntry>> {
+    spec.split(',')
+        .map(|entry| {
+            let parts: Vec<&str> = entry.trim().split(':').collect();
+            let [name, symbol, span, count] = parts[..] else {
+                anyhow::bail!("Invalid fleet entry '{}'. Use: name:symbol:length:count", entry);
+            };
+
+            let mut symbols = symbol.trim().chars();
+            let symbol = match (symbols.next(), symbols.next()) {
+                (Some(c), None) => c.to_ascii_uppercase(),
+                _ => anyhow::bail!("Ship symbol '{}' must be a single letter", symbol),
+            };
+
+            Ok(FleetEntry {
+                class: ShipClass::new(name.trim(), symbol, span.trim().parse()?),
+                count: count.trim().parse()?,
+            })
+        })
+        .collect()
+}
+
+fn prompt(message: &str) -> anyhow::Result<String> {
+    print!("{}", message);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
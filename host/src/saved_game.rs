@@ -0,0 +1,76 @@
+use crate::board_display::BoardDisplay;
+use crate::keys::{key_dir, write_private};
+use crate::network_protocol::GameMessage;
+use crate::verification::{ImageIds, ReceiptKind, VerificationPolicy};
+use anyhow::Context;
+use battleship_core::game::Match;
+use ed25519_dalek::VerifyingKey;
+use risc0_zkvm::sha::Digest;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Everything needed to pick a game back up after the connection or the
+/// program dies. Holds the secret board, so it is only readable by us.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    /// The match as of the last completed step, our secret board included
+    pub game: Match,
+    pub policy: VerificationPolicy,
+    pub peer_ids: ImageIds,
+    /// The kind of receipt we prove, and the kinds we accept from the opponent
+    pub receipt_kind: ReceiptKind,
+    pub accepted_kinds: Vec<ReceiptKind>,
+    pub peer_identity: VerifyingKey,
+    pub player_name: String,
+    pub opponent_name: String,
+
+    pub my_display: BoardDisplay,
+    pub opponent_display: BoardDisplay,
+    /// Our last `ShotResult`/`SalvoResult`, resent if the opponent never got it
+    pub last_result: Option<GameMessage>,
+    /// Length of the transcript file when saved; anything after it is dropped on resume
+    pub transcript_len: u64,
+
+    /// Signed message chains, see `SignedTransport::chains`
+    pub sent_chain: Vec<Digest>,
+    pub received_chain: Vec<Digest>,
+}
+
+impl SavedGame {
+    /// Where the game with this session is saved
+    pub fn path(session: &Digest) -> PathBuf {
+        saves_dir().join(format!("{}.json", session))
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read saved game {}", path.display()))?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn store(&self) -> anyhow::Result<()> {
+        write_private(&Self::path(&self.game.session()), &serde_json::to_string(self)?)
+    }
+
+    /// Saved games, most recent first
+    pub fn list() -> anyhow::Result<Vec<PathBuf>> {
+        let Ok(entries) = std::fs::read_dir(saves_dir()) else {
+            return Ok(Vec::new());
+        };
+
+        let mut saves = entries
+            .map(|entry| {
+                let entry = entry?;
+                Ok((entry.metadata()?.modified()?, entry.path()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        // A save interrupted mid-write leaves a `.json.tmp` behind
+        saves.retain(|(_, path)| path.extension().is_some_and(|ext| ext == "json"));
+        saves.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        Ok(saves.into_iter().map(|(_, path)| path).collect())
+    }
+}
+
+fn saves_dir() -> PathBuf {
+    key_dir().join("saves")
+}
@@ -1,7 +1,9 @@
-use battleship_core::{Direction, GameState, Position, Ship, ShipClass, BOARD_SIZE};
+use crate::board_file;
+use battleship_core::{Direction, GameRules, GameState, Position, Ship};
 use std::io::{self, Write};
+use std::path::Path;
 
-pub fn interactive_ship_placement() -> anyhow::Result<GameState> {
+pub fn interactive_ship_placement(rules: &GameRules) -> anyhow::Result<GameState> {
     println!("\n╔═══════════════════════════════════════════════╗");
     println!("║       SHIP PLACEMENT - Zero-Knowledge         ║");
     println!("║  Your board will be cryptographically         ║");
@@ -11,25 +13,72 @@ pub fn interactive_ship_placement() -> anyhow::Result<GameState> {
     println!("Choose placement method:");
     println!("  1. Manual placement (choose each ship position)");
     println!("  2. Random placement (quick setup for testing)");
-    print!("\nEnter choice (1/2): ");
+    println!("  3. Load a board file");
+    print!("\nEnter choice (1/2/3): ");
     io::stdout().flush()?;
 
     let mut choice = String::new();
     io::stdin().read_line(&mut choice)?;
 
-    match choice.trim() {
-        "1" => manual_placement(),
-        "2" => random_placement(),
+    let state = match choice.trim() {
+        "1" => manual_placement(rules)?,
+        "2" => random_placement(rules)?,
+        "3" => return load_placement(rules),
         _ => {
             println!("Invalid choice, using random placement");
-            random_placement()
+            random_placement(rules)?
+        }
+    };
+    export_placement(&state)?;
+    Ok(state)
+}
+
+/// Ask for a board file until one loads
+fn load_placement(rules: &GameRules) -> anyhow::Result<GameState> {
+    loop {
+        print!("Board file (.json for JSON, anything else a text grid): ");
+        io::stdout().flush()?;
+        let mut path = String::new();
+        io::stdin().read_line(&mut path)?;
+
+        match board_file::load(Path::new(path.trim()), rules) {
+            Ok(state) => {
+                display_board(&state);
+                println!("\n✅ Fleet loaded from {}", path.trim());
+                return Ok(state);
+            }
+            Err(e) => println!("✗ {:#}", e),
+        }
+    }
+}
+
+/// Offer to save the layout so it can be reused with `--board`, asking again
+/// if it cannot be written; the fleet is placed either way
+fn export_placement(state: &GameState) -> anyhow::Result<()> {
+    loop {
+        print!("Save this layout to a board file (Enter to skip): ");
+        io::stdout().flush()?;
+        let mut path = String::new();
+        io::stdin().read_line(&mut path)?;
+
+        let path = path.trim();
+        if path.is_empty() {
+            return Ok(());
+        }
+        match board_file::save(Path::new(path), state) {
+            Ok(()) => {
+                println!("✓ Layout saved to {}", path);
+                return Ok(());
+            }
+            Err(e) => println!("✗ {:#}", e),
         }
     }
 }
 
-fn random_placement() -> anyhow::Result<GameState> {
+fn random_placement(rules: &GameRules) -> anyhow::Result<GameState> {
     println!("\n🎲 Generating random ship placement...");
-    let state: GameState = rand::random();
+    let state = GameState::random(rules, &mut rand::thread_rng())
+        .ok_or_else(|| anyhow::anyhow!("Could not fit the fleet on the board, try manual placement"))?;
     
     display_board(&state);
     println!("\n✅ Ships randomly placed!");
@@ -41,26 +90,19 @@ fn random_placement() -> anyhow::Result<GameState> {
     Ok(state)
 }
 
-fn manual_placement() -> anyhow::Result<GameState> {
-    let mut state = GameState::new(rand::random());
-
-    let ships_to_place = [
-        (ShipClass::Carrier, "Carrier", 5),
-        (ShipClass::Battleship, "Battleship", 4),
-        (ShipClass::Cruiser, "Cruiser", 3),
-        (ShipClass::Submarine, "Submarine", 3),
-        (ShipClass::Destroyer, "Destroyer", 2),
-    ];
+pub fn manual_placement(rules: &GameRules) -> anyhow::Result<GameState> {
+    let mut state = GameState::new(rules.clone(), rand::random());
 
-    for (ship_class, name, length) in ships_to_place {
+    for ship_class in rules.ships_to_place() {
+        let name = &ship_class.name;
         loop {
             display_board(&state);
             println!("\n┌─────────────────────────────────────┐");
-            println!("│ Placing: {} (length: {})        ", name, length);
+            println!("│ Placing: {} (length: {})        ", name, ship_class.span);
             println!("└─────────────────────────────────────┘");
 
             // Get position
-            let pos = match prompt_position("Enter starting position (x,y): ")? {
+            let pos = match prompt_position("Enter starting position (x,y): ", rules)? {
                 Some(p) => p,
                 None => continue,
             };
@@ -72,15 +114,18 @@ fn manual_placement() -> anyhow::Result<GameState> {
             };
 
             // Try to place the ship
-            let ship = Ship::new(ship_class, pos, dir);
-            if state.add_ship(ship) {
-                println!("✓ {} placed successfully!", name);
-                break;
-            } else {
-                println!("✗ Invalid placement! Ship overlaps or goes out of bounds.");
-                println!("  Press Enter to try again...");
-                let mut buffer = String::new();
-                io::stdin().read_line(&mut buffer)?;
+            let ship = Ship::new(ship_class.clone(), pos, dir);
+            match state.add_ship(ship) {
+                Ok(()) => {
+                    println!("✓ {} placed successfully!", name);
+                    break;
+                }
+                Err(e) => {
+                    println!("✗ Invalid placement: {}", e);
+                    println!("  Press Enter to try again...");
+                    let mut buffer = String::new();
+                    io::stdin().read_line(&mut buffer)?;
+                }
             }
         }
     }
@@ -92,46 +137,36 @@ fn manual_placement() -> anyhow::Result<GameState> {
 }
 
 fn display_board(state: &GameState) {
-    println!("\n  ┌────────────────────────────────┐");
-    print!("  │ ");
-    for x in 0..BOARD_SIZE {
-        print!(" {} ", x);
+    let rules = &state.rules;
+    let label = (rules.height - 1).to_string().len();
+    let grid = 3 * rules.width as usize + 2;
+
+    println!("\n{}┌{}┐", " ".repeat(label + 1), "─".repeat(grid));
+    print!("{}│ ", " ".repeat(label + 1));
+    for x in 0..rules.width {
+        print!("{:^3}", x);
     }
     println!(" │");
-    println!("  ├────────────────────────────────┤");
-
-    for y in 0..BOARD_SIZE {
-        print!("{} │ ", y);
-        for x in 0..BOARD_SIZE {
-            let pos = Position::new(x as u32, y as u32);
-            let mut found = false;
-            
-            for ship in &state.ships {
-                if ship.points().any(|p| p == pos) {
-                    let symbol = match ship.class {
-                        ShipClass::Carrier => "A",
-                        ShipClass::Battleship => "B",
-                        ShipClass::Cruiser => "C",
-                        ShipClass::Submarine => "S",
-                        ShipClass::Destroyer => "D",
-                    };
-                    print!(" {} ", symbol);
-                    found = true;
-                    break;
-                }
-            }
-            
-            if !found {
-                print!(" ~ ");
-            }
+    println!("{}├{}┤", " ".repeat(label + 1), "─".repeat(grid));
+
+    for y in 0..rules.height {
+        print!("{:>label$} │ ", y);
+        for x in 0..rules.width {
+            let pos = Position::new(x, y);
+            let symbol = state
+                .ships
+                .iter()
+                .find(|ship| ship.points().any(|p| p == pos))
+                .map_or('~', |ship| ship.class.symbol);
+            print!(" {} ", symbol);
         }
         println!(" │");
     }
 
-    println!("  └────────────────────────────────┘");
+    println!("{}└{}┘", " ".repeat(label + 1), "─".repeat(grid));
 }
 
-fn prompt_position(prompt: &str) -> anyhow::Result<Option<Position>> {
+fn prompt_position(prompt: &str, rules: &GameRules) -> anyhow::Result<Option<Position>> {
     print!("{}", prompt);
     io::stdout().flush()?;
 
@@ -147,17 +182,17 @@ fn prompt_position(prompt: &str) -> anyhow::Result<Option<Position>> {
     }
 
     let x: u32 = match parts[0].trim().parse() {
-        Ok(v) if v < BOARD_SIZE as u32 => v,
+        Ok(v) if v < rules.width => v,
         _ => {
-            println!("✗ X must be between 0 and {}", BOARD_SIZE - 1);
+            println!("✗ X must be between 0 and {}", rules.width - 1);
             return Ok(None);
         }
     };
 
     let y: u32 = match parts[1].trim().parse() {
-        Ok(v) if v < BOARD_SIZE as u32 => v,
+        Ok(v) if v < rules.height => v,
         _ => {
-            println!("✗ Y must be between 0 and {}", BOARD_SIZE - 1);
+            println!("✗ Y must be between 0 and {}", rules.height - 1);
             return Ok(None);
         }
     };
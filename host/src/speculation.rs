@@ -0,0 +1,255 @@
+use crate::prover::{ProofRequest, ShotProver};
+use crate::verification::ReceiptKind;
+use risc0_zkvm::Receipt;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// How well speculative proving has guessed the opponent's shots
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SpeculationStats {
+    /// Results that were proven, or being proven, before the shot arrived
+    pub hits: u32,
+    /// Results that had to be proven from scratch
+    pub misses: u32,
+    /// Proofs made for shots that never came
+    pub wasted: u32,
+    /// Proving time the opponent did not have to wait for
+    pub time_saved: Duration,
+}
+
+impl SpeculationStats {
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+impl Display for SpeculationStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} results proven ahead ({:.0}%), {:.1}s of proving saved, {} proofs wasted",
+            self.hits,
+            self.hits + self.misses,
+            self.hit_rate() * 100.0,
+            self.time_saved.as_secs_f64(),
+            self.wasted
+        )
+    }
+}
+
+/// Proves guessed requests in the background while the opponent decides, so
+/// the proof for the shot that comes is often ready, or at least under way.
+///
+/// Requests are told apart by their serialized form, so a guess only counts
+/// when it matches the real request exactly: same session, turn, board and shot.
+pub struct SpeculativeProver {
+    inner: Arc<dyn ShotProver>,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    cache: Mutex<Cache>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct Cache {
+    capacity: usize,
+    /// Guesses still to prove, most likely first
+    queue: VecDeque<(Vec<u8>, ProofRequest, ReceiptKind)>,
+    /// The guess the worker is proving and when it started
+    proving: Option<(Vec<u8>, Instant)>,
+    /// Finished guesses with how long each took, oldest first
+    receipts: VecDeque<(Vec<u8>, Receipt, Duration)>,
+    stats: SpeculationStats,
+    stopped: bool,
+}
+
+impl SpeculativeProver {
+    /// Speculate with `inner`, keeping at most `capacity` guesses queued or proven
+    pub fn new(inner: Arc<dyn ShotProver>, capacity: usize) -> Self {
+        let shared = Arc::new(Shared {
+            cache: Mutex::new(Cache {
+                capacity,
+                ..Cache::default()
+            }),
+            changed: Condvar::new(),
+        });
+
+        let worker = (inner.clone(), shared.clone());
+        std::thread::spawn(move || work(worker.0.as_ref(), &worker.1));
+        Self { inner, shared }
+    }
+
+    /// Replace the guesses with `requests`, most likely first. Receipts for
+    /// earlier guesses are dropped, since the board they were made on has moved on.
+    pub fn speculate(&self, requests: Vec<ProofRequest>, kind: ReceiptKind) {
+        let mut cache = self.shared.lock();
+        let keys: Vec<Vec<u8>> = requests.iter().map(|request| key(request, kind)).collect();
+
+        let before = cache.receipts.len();
+        cache.receipts.retain(|(key, _, _)| keys.contains(key));
+        cache.stats.wasted += (before - cache.receipts.len()) as u32;
+
+        let capacity = cache.capacity.saturating_sub(cache.receipts.len());
+        let queue = keys
+            .into_iter()
+            .zip(requests)
+            .filter(|(key, _)| {
+                !cache.receipts.iter().any(|(cached, _, _)| cached == key)
+                    && cache.proving.as_ref().is_none_or(|(proving, _)| proving != key)
+            })
+            .take(capacity)
+            .map(|(key, request)| (key, request, kind))
+            .collect();
+        cache.queue = queue;
+        self.shared.changed.notify_all();
+    }
+
+    /// How many guesses are worth making at once
+    pub fn capacity(&self) -> usize {
+        self.shared.lock().capacity
+    }
+
+    pub fn stats(&self) -> SpeculationStats {
+        self.shared.lock().stats.clone()
+    }
+}
+
+impl ShotProver for SpeculativeProver {
+    fn prove(&self, request: &ProofRequest, kind: ReceiptKind) -> anyhow::Result<Receipt> {
+        let wanted = key(request, kind);
+        let mut cache = self.shared.lock();
+        // The request is here, so the other guesses are for a turn that did not happen
+        cache.queue.clear();
+
+        // Caught mid-proof, only the part already done is saved
+        let mut done_already = None;
+        while let Some((proving, started)) = &cache.proving {
+            if *proving != wanted {
+                break;
+            }
+            done_already.get_or_insert(started.elapsed());
+            cache = self.shared.changed.wait(cache).expect("speculation lock poisoned");
+        }
+
+        if let Some(i) = cache.receipts.iter().position(|(key, _, _)| *key == wanted) {
+            let (_, receipt, took) = cache.receipts.remove(i).expect("position is in range");
+            cache.stats.hits += 1;
+            cache.stats.time_saved += done_already.unwrap_or(took);
+            return Ok(receipt);
+        }
+
+        cache.stats.misses += 1;
+        drop(cache);
+        self.inner.prove(request, kind)
+    }
+}
+
+impl Drop for SpeculativeProver {
+    fn drop(&mut self) {
+        let mut cache = self.shared.lock();
+        cache.stopped = true;
+        cache.stats.wasted += (cache.receipts.len() + cache.queue.len()) as u32;
+        cache.queue.clear();
+        self.shared.changed.notify_all();
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Cache> {
+        self.cache.lock().expect("speculation lock poisoned")
+    }
+}
+
+fn key(request: &ProofRequest, kind: ReceiptKind) -> Vec<u8> {
+    bincode::serialize(&(request, kind)).expect("proof requests always serialize")
+}
+
+/// Prove queued guesses one at a time until the prover is dropped
+fn work(prover: &dyn ShotProver, shared: &Shared) {
+    let mut cache = shared.lock();
+    loop {
+        if cache.stopped {
+            return;
+        }
+        let Some((key, request, kind)) = cache.queue.pop_front() else {
+            cache = shared.changed.wait(cache).expect("speculation lock poisoned");
+            continue;
+        };
+
+        let started = Instant::now();
+        cache.proving = Some((key.clone(), started));
+        drop(cache);
+        // A guess the guest refuses, or a prover that fails, is just a miss later
+        let receipt = prover.prove(&request, kind);
+
+        cache = shared.lock();
+        cache.proving = None;
+        if let Ok(receipt) = receipt {
+            if cache.receipts.len() >= cache.capacity.max(1) {
+                cache.receipts.pop_front();
+                cache.stats.wasted += 1;
+            }
+            cache.receipts.push_back((key, receipt, started.elapsed()));
+        }
+        shared.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prover::FakeProver;
+    use battleship_core::{GameRules, GameState, Position, RoundInput};
+    use risc0_zkvm::sha::Digest;
+
+    const KIND: ReceiptKind = ReceiptKind::Succinct;
+
+    fn round(state: &GameState, x: u32) -> ProofRequest {
+        ProofRequest::Round(RoundInput {
+            session: Digest::default(),
+            turn: 0,
+            state: state.clone(),
+            shot: Position::new(x, 0),
+        })
+    }
+
+    /// Wait until the worker has proven every guess
+    fn settle(prover: &SpeculativeProver) {
+        let mut cache = prover.shared.lock();
+        while !cache.queue.is_empty() || cache.proving.is_some() {
+            cache = prover.shared.changed.wait(cache).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_speculation() {
+        let state = GameState::random(&GameRules::classic(), &mut rand::thread_rng()).unwrap();
+        let prover = SpeculativeProver::new(Arc::new(FakeProver), 2);
+
+        // Only as many guesses as the cache holds are proven
+        prover.speculate((0..4).map(|x| round(&state, x)).collect(), KIND);
+        settle(&prover);
+        assert_eq!(prover.shared.lock().receipts.len(), 2);
+
+        let receipt = prover.prove(&round(&state, 1), KIND).unwrap();
+        assert_eq!(receipt.journal.bytes, round(&state, 1).journal().unwrap());
+        prover.prove(&round(&state, 3), KIND).unwrap();
+        // A guess only counts for the kind it was proven as
+        prover.prove(&round(&state, 0), ReceiptKind::Composite).unwrap();
+
+        // The next guesses drop the receipt nobody asked for
+        prover.speculate(vec![round(&state, 5)], KIND);
+        settle(&prover);
+
+        let stats = prover.stats();
+        assert_eq!((stats.hits, stats.misses, stats.wasted), (1, 2, 1));
+        assert!((stats.hit_rate() - 1.0 / 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,611 @@
+use crate::framing::{read_frame, write_frame, FrameError, DEFAULT_MAX_FRAME};
+use crate::keys::key_dir;
+use crate::network_protocol::{GameMessage, SignedGameResult};
+use crate::verification::{ImageIds, VerificationPolicy};
+use anyhow::Context;
+use battleship_core::{
+    audit_game, audit_salvo_game, BoardCommit, GameMode, GameRules, HitType, Position,
+    RoundCommit, SalvoCommit,
+};
+use risc0_zkvm::{sha::Digest, Receipt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// What a transcript is about. Player 0 is the player who moved first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptHeader {
+    pub session: Digest,
+    pub rules: GameRules,
+    /// Guest image IDs each player's receipts were verified against
+    pub image_ids: [ImageIds; 2],
+}
+
+/// A message that moved the game along, and which player sent it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub player: usize,
+    pub message: GameMessage,
+}
+
+/// One frame of a transcript file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Record {
+    Header(TranscriptHeader),
+    Message(Entry),
+    /// The result both players signed at the end
+    Outcome(Box<SignedGameResult>),
+}
+
+/// Where the transcript of a session is written
+pub fn path(session: &Digest) -> PathBuf {
+    key_dir().join("transcripts").join(format!("{}.bin", session))
+}
+
+/// Appends a game's records to its transcript file as it is played, so a game
+/// that ends in a dispute is on disk up to the message in question
+pub struct TranscriptWriter {
+    file: File,
+}
+
+impl TranscriptWriter {
+    pub fn create(path: &Path, header: TranscriptHeader) -> anyhow::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create transcript {}", path.display()))?;
+
+        let mut writer = Self { file };
+        writer.append(&Record::Header(header))?;
+        Ok(writer)
+    }
+
+    /// Continue a resumed game's transcript, dropping whatever was written
+    /// after the game was saved at `len` bytes
+    pub fn reopen(path: &Path, len: u64) -> anyhow::Result<Self> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(path)
+            .with_context(|| format!("Failed to open transcript {}", path.display()))?;
+        file.set_len(len)?;
+        file.seek(SeekFrom::End(0))?;
+        Ok(Self { file })
+    }
+
+    pub fn bytes_written(&self) -> anyhow::Result<u64> {
+        Ok(self.file.metadata()?.len())
+    }
+
+    /// Record `message` if it is part of the game proper; handshakes,
+    /// signatures and errors are left out
+    pub fn record(&mut self, player: usize, message: &GameMessage) -> anyhow::Result<()> {
+        let recorded = matches!(
+            message,
+            GameMessage::BoardReady { .. }
+                | GameMessage::TakeShot { .. }
+                | GameMessage::ShotResult { .. }
+                | GameMessage::TakeShots { .. }
+                | GameMessage::SalvoResult { .. }
+                | GameMessage::GameOver { .. }
+                | GameMessage::RevealBoard { .. }
+        );
+        if !recorded {
+            return Ok(());
+        }
+        self.append(&Record::Message(Entry {
+            player,
+            message: message.clone(),
+        }))
+    }
+
+    pub fn finish(&mut self, outcome: &SignedGameResult) -> anyhow::Result<()> {
+        self.append(&Record::Outcome(Box::new(outcome.clone())))
+    }
+
+    fn append(&mut self, record: &Record) -> anyhow::Result<()> {
+        write_frame(&mut self.file, &bincode::serialize(record)?, DEFAULT_MAX_FRAME)
+    }
+}
+
+/// A transcript read back from disk
+pub struct Transcript {
+    pub header: TranscriptHeader,
+    pub entries: Vec<Entry>,
+    pub outcome: Option<SignedGameResult>,
+}
+
+impl Transcript {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open transcript {}", path.display()))?;
+        let mut reader = BufReader::new(file);
+
+        let mut records = Vec::new();
+        loop {
+            match read_frame(&mut reader, DEFAULT_MAX_FRAME) {
+                Ok(frame) => records.push(bincode::deserialize::<Record>(&frame)?),
+                Err(e) if e.downcast_ref() == Some(&FrameError::Closed) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut records = records.into_iter();
+        let Some(Record::Header(header)) = records.next() else {
+            anyhow::bail!("{} does not start with a transcript header", path.display());
+        };
+
+        let mut entries = Vec::new();
+        let mut outcome = None;
+        for record in records {
+            match record {
+                Record::Message(entry) if outcome.is_none() => entries.push(entry),
+                Record::Outcome(result) if outcome.is_none() => outcome = Some(*result),
+                _ => anyhow::bail!("{} has records out of order", path.display()),
+            }
+        }
+
+        Ok(Self {
+            header,
+            entries,
+            outcome,
+        })
+    }
+
+    /// Check every receipt and that each board's state chain is unbroken from
+    /// its commitment through every proven shot, stopping at the first invalid step
+    pub fn verify(&self, policy: &VerificationPolicy) -> Result<Verified, InvalidStep> {
+        let mut checker = Checker {
+            header: &self.header,
+            policy,
+            boards: Default::default(),
+            to_move: 0,
+        };
+
+        for (player, ids) in self.header.image_ids.iter().enumerate() {
+            if !policy.accepts_ids(ids) {
+                return Err(InvalidStep {
+                    step: 0,
+                    description: "header".to_string(),
+                    reason: format!(
+                        "player {}'s guest image IDs [{}] are not accepted under the {} policy",
+                        player + 1,
+                        ids,
+                        policy
+                    ),
+                });
+            }
+        }
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            checker.step(entry).map_err(|e| InvalidStep {
+                step: i + 1,
+                description: checker.describe(entry),
+                reason: format!("{:#}", e),
+            })?;
+        }
+
+        if let Some(outcome) = &self.outcome {
+            checker.check_outcome(outcome).map_err(|e| InvalidStep {
+                step: self.entries.len() + 1,
+                description: "signed result".to_string(),
+                reason: format!("{:#}", e),
+            })?;
+        }
+
+        let [first, second] = &checker.boards;
+        Ok(Verified {
+            names: [first.name.clone(), second.name.clone()],
+            turns: [first.turns(), second.turns()],
+            winner: checker.winner().map(|player| checker.boards[player].name.clone()),
+            audited: [first.revealed, second.revealed],
+            signed: self.outcome.is_some(),
+        })
+    }
+
+    /// Verify the transcript and print the outcome for a referee, returning whether it is valid
+    pub fn print_report(&self, policy: &VerificationPolicy) -> bool {
+        let header = &self.header;
+        println!("Session:  {}", header.session);
+        println!(
+            "Rules:    {}x{} board, {} ships, {} mode",
+            header.rules.width,
+            header.rules.height,
+            header.rules.total_ships(),
+            header.rules.mode
+        );
+        println!("Policy:   {}", policy.trust_level());
+        println!("Steps:    {}\n", self.entries.len());
+
+        let verified = match self.verify(policy) {
+            Ok(verified) => verified,
+            Err(invalid) => {
+                println!("❌ INVALID at {}", invalid);
+                return false;
+            }
+        };
+
+        for (player, name) in verified.names.iter().enumerate() {
+            println!(
+                "✅ {}: board proven, {} proven turns{}",
+                name,
+                verified.turns[player],
+                if verified.audited[player] { ", revealed board audited" } else { "" }
+            );
+        }
+        match &verified.winner {
+            Some(winner) => println!("🏆 {} won; the loser proved their fleet sunk", winner),
+            None => println!("⏸  The game did not finish"),
+        }
+        if verified.signed {
+            println!("📝 Result signed by both players");
+        }
+        true
+    }
+}
+
+/// Summary of a transcript that checked out
+#[derive(Debug)]
+pub struct Verified {
+    pub names: [String; 2],
+    /// Proven turns against each player's board
+    pub turns: [usize; 2],
+    /// Set once the loser proved their fleet sunk
+    pub winner: Option<String>,
+    /// Whose revealed board was replayed against their proven turns
+    pub audited: [bool; 2],
+    pub signed: bool,
+}
+
+/// The first step of a transcript that does not hold up; step 0 is the header
+#[derive(Debug)]
+pub struct InvalidStep {
+    pub step: usize,
+    pub description: String,
+    pub reason: String,
+}
+
+impl Display for InvalidStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "step {} ({}): {}", self.step, self.description, self.reason)
+    }
+}
+
+impl std::error::Error for InvalidStep {}
+
+/// What the transcript has established about one player's board
+#[derive(Default)]
+struct Board {
+    name: String,
+    initial: Option<Digest>,
+    current: Digest,
+    rounds: Vec<RoundCommit>,
+    salvos: Vec<SalvoCommit>,
+    /// Shots fired at this board that have not been answered yet
+    incoming: Vec<Position>,
+    sunk: bool,
+    revealed: bool,
+}
+
+impl Board {
+    fn turns(&self) -> usize {
+        self.rounds.len() + self.salvos.len()
+    }
+}
+
+struct Checker<'a> {
+    header: &'a TranscriptHeader,
+    policy: &'a VerificationPolicy,
+    boards: [Board; 2],
+    /// Player whose turn it is to fire
+    to_move: usize,
+}
+
+impl Checker<'_> {
+    fn step(&mut self, entry: &Entry) -> anyhow::Result<()> {
+        let player = entry.player;
+        if player > 1 {
+            anyhow::bail!("sent by unknown player {}", player);
+        }
+        let opponent = 1 - player;
+        let ids = self.header.image_ids[player];
+
+        if !matches!(entry.message, GameMessage::BoardReady { .. })
+            && self.boards.iter().any(|board| board.initial.is_none())
+        {
+            anyhow::bail!("the game continued before both boards were committed");
+        }
+
+        match &entry.message {
+            GameMessage::BoardReady { commitment, player_name, proof } => {
+                if self.boards[player].initial.is_some() {
+                    anyhow::bail!("board committed twice");
+                }
+                let proven: BoardCommit = self.open(proof.to_receipt(), ids.init)?;
+                self.check_board(&proven, commitment)?;
+
+                let board = &mut self.boards[player];
+                board.name = player_name.clone();
+                board.initial = Some(*commitment);
+                board.current = *commitment;
+            }
+            GameMessage::TakeShot { position } => {
+                self.check_fire(player, GameMode::Classic)?;
+                self.boards[opponent].incoming = vec![*position];
+            }
+            GameMessage::TakeShots { positions } => {
+                let afloat = match self.boards[player].salvos.last() {
+                    Some(salvo) => salvo.ships_afloat as usize,
+                    None => self.header.rules.total_ships(),
+                };
+                self.check_fire(player, GameMode::Salvo)?;
+                if positions.len() != afloat {
+                    anyhow::bail!("salvo of {} shots with {} ships afloat", positions.len(), afloat);
+                }
+                self.boards[opponent].incoming = positions.clone();
+            }
+            GameMessage::ShotResult { position, hit_type, proof } => {
+                let commit: RoundCommit = self.open(proof.to_receipt(), ids.round)?;
+                let board = &mut self.boards[player];
+                if board.incoming != [*position] {
+                    anyhow::bail!("answers a shot at {} that was not fired", position);
+                }
+                check_chain(commit.session, commit.turn, commit.old_state, self.header, board)?;
+                if commit.shot != *position || commit.hit != *hit_type || proof.commit != commit {
+                    anyhow::bail!("receipt proves {:?} at {}, not the result sent", commit.hit, commit.shot);
+                }
+
+                board.current = commit.new_state;
+                board.incoming.clear();
+                board.rounds.push(commit);
+                if matches!(hit_type, HitType::Miss | HitType::Repeat) {
+                    self.to_move = player;
+                }
+            }
+            GameMessage::SalvoResult { positions, hit_types, proof } => {
+                let commit: SalvoCommit = self.open(proof.to_receipt(), ids.salvo)?;
+                let board = &mut self.boards[player];
+                if board.incoming.is_empty() || board.incoming != *positions {
+                    anyhow::bail!("answers a salvo that was not fired");
+                }
+                check_chain(commit.session, commit.turn, commit.old_state, self.header, board)?;
+                if commit.shots != *positions || commit.hits != *hit_types || proof.commit != commit {
+                    anyhow::bail!("receipt proves a different salvo result than the one sent");
+                }
+
+                board.current = commit.new_state;
+                board.incoming.clear();
+                board.salvos.push(commit);
+                self.to_move = player;
+            }
+            GameMessage::GameOver { winner, proof } => {
+                let proven: BoardCommit = self.open(proof.to_receipt(), ids.fleet_sunk)?;
+                let current = self.boards[player].current;
+                self.check_board(&proven, &current)?;
+                if *winner != self.boards[opponent].name {
+                    anyhow::bail!("names {} as the winner", winner);
+                }
+                self.boards[player].sunk = true;
+            }
+            GameMessage::RevealBoard { state } => {
+                let board = &mut self.boards[player];
+                let initial = board.initial.expect("checked above");
+                let audit = match self.header.rules.mode {
+                    GameMode::Classic => {
+                        audit_game(state, &initial, &self.header.session, &board.rounds)
+                    }
+                    GameMode::Salvo => {
+                        audit_salvo_game(state, &initial, &self.header.session, &board.salvos)
+                    }
+                };
+                audit.map_err(|e| anyhow::anyhow!("revealed board fails the audit: {}", e))?;
+                board.revealed = true;
+            }
+            other => anyhow::bail!("unexpected message {}", message_name(other)),
+        }
+        Ok(())
+    }
+
+    /// Verify a receipt under the policy and decode its journal
+    fn open<J: DeserializeOwned>(
+        &self,
+        receipt: anyhow::Result<Receipt>,
+        image_id: Digest,
+    ) -> anyhow::Result<J> {
+        let receipt = receipt.map_err(|e| anyhow::anyhow!("malformed receipt: {}", e))?;
+        self.policy
+            .verify(&receipt, image_id)
+            .map_err(|e| anyhow::anyhow!("receipt does not verify: {}", e))?;
+        Ok(receipt.journal.decode()?)
+    }
+
+    fn check_board(&self, proven: &BoardCommit, commitment: &Digest) -> anyhow::Result<()> {
+        if proven.session != self.header.session {
+            anyhow::bail!("receipt was proven for a different game session");
+        }
+        if proven.rules != self.header.rules {
+            anyhow::bail!("board was checked against different rules");
+        }
+        if proven.state != *commitment {
+            anyhow::bail!("receipt proves board {} but {} was expected", proven.state, commitment);
+        }
+        Ok(())
+    }
+
+    fn check_fire(&self, player: usize, mode: GameMode) -> anyhow::Result<()> {
+        if self.header.rules.mode != mode {
+            anyhow::bail!("not a {} mode move", mode);
+        }
+        if self.boards.iter().any(|board| board.sunk) {
+            anyhow::bail!("fired after the game was over");
+        }
+        if player != self.to_move {
+            anyhow::bail!("fired out of turn");
+        }
+        if !self.boards[1 - player].incoming.is_empty() {
+            anyhow::bail!("fired again before the last shot was answered");
+        }
+        Ok(())
+    }
+
+    fn check_outcome(&self, outcome: &SignedGameResult) -> anyhow::Result<()> {
+        outcome.verify()?;
+        let result = &outcome.result;
+        if result.session != self.header.session {
+            anyhow::bail!("result is for a different game session");
+        }
+        for (player, board) in self.boards.iter().enumerate() {
+            if Some(result.commitments[player]) != board.initial {
+                anyhow::bail!("result lists a different board for {}", board.name);
+            }
+            if result.names[player] != board.name {
+                anyhow::bail!("result names {} instead of {}", result.names[player], board.name);
+            }
+        }
+        match self.winner() {
+            Some(winner) if result.winner == result.players[winner] => Ok(()),
+            Some(_) => anyhow::bail!("result names the player who conceded as the winner"),
+            None => anyhow::bail!("result was signed before anyone conceded"),
+        }
+    }
+
+    fn winner(&self) -> Option<usize> {
+        self.boards.iter().position(|board| board.sunk).map(|loser| 1 - loser)
+    }
+
+    fn describe(&self, entry: &Entry) -> String {
+        let sender = match self.boards.get(entry.player) {
+            Some(board) if !board.name.is_empty() => board.name.clone(),
+            _ => format!("player {}", entry.player + 1),
+        };
+        let what = match &entry.message {
+            GameMessage::TakeShot { position } => format!("shot at {}", position),
+            GameMessage::ShotResult { position, .. } => format!("result for {}", position),
+            other => message_name(other).to_string(),
+        };
+        format!("{} from {}", what, sender)
+    }
+}
+
+/// The next link in a board's chain must continue from its current state
+fn check_chain(
+    session: Digest,
+    turn: u32,
+    old_state: Digest,
+    header: &TranscriptHeader,
+    board: &Board,
+) -> anyhow::Result<()> {
+    if session != header.session {
+        anyhow::bail!("receipt was proven for a different game session");
+    }
+    if turn as usize != board.turns() {
+        anyhow::bail!("receipt is for turn {} but turn {} was next", turn, board.turns());
+    }
+    if old_state != board.current {
+        anyhow::bail!(
+            "chain is broken: receipt starts from {} but the board was at {}",
+            old_state,
+            board.current
+        );
+    }
+    Ok(())
+}
+
+fn message_name(message: &GameMessage) -> &'static str {
+    match message {
+        GameMessage::Hello { .. } => "Hello",
+        GameMessage::BoardReady { .. } => "BoardReady",
+        GameMessage::TakeShot { .. } => "TakeShot",
+        GameMessage::ShotResult { .. } => "ShotResult",
+        GameMessage::TakeShots { .. } => "TakeShots",
+        GameMessage::SalvoResult { .. } => "SalvoResult",
+        GameMessage::GameOver { .. } => "GameOver",
+        GameMessage::RevealBoard { .. } => "RevealBoard",
+        GameMessage::Resume { .. } => "Resume",
+        GameMessage::ResultSignature { .. } => "ResultSignature",
+        GameMessage::Error { .. } => "Error",
+        GameMessage::Signed { .. } => "Signed",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network_protocol::{BoardProof, ProofData};
+    use battleship_core::GameState;
+
+    fn header() -> TranscriptHeader {
+        TranscriptHeader {
+            session: Digest::from([1u32; 8]),
+            rules: GameRules::classic(),
+            image_ids: [ImageIds::local(); 2],
+        }
+    }
+
+    #[test]
+    fn test_transcript_file() {
+        let path = std::env::temp_dir().join(format!("battleship-transcript-{}.bin", std::process::id()));
+        let mut writer = TranscriptWriter::create(&path, header()).unwrap();
+
+        let board = GameMessage::BoardReady {
+            commitment: Digest::default(),
+            player_name: "Alice".to_string(),
+            proof: BoardProof { receipt_bytes: vec![] },
+        };
+        writer.record(0, &board).unwrap();
+        writer.record(0, &GameMessage::Error { message: "ignored".to_string() }).unwrap();
+        let saved_at = writer.bytes_written().unwrap();
+        writer.record(1, &GameMessage::TakeShot { position: Position::new(1, 2) }).unwrap();
+        assert_eq!(Transcript::load(&path).unwrap().entries.len(), 2);
+
+        // Resuming drops what was written after the save
+        let mut writer = TranscriptWriter::reopen(&path, saved_at).unwrap();
+        writer.record(1, &GameMessage::RevealBoard { state: GameState::new(GameRules::classic(), [0; 16]) }).unwrap();
+        let transcript = Transcript::load(&path).unwrap();
+        assert_eq!(transcript.entries.len(), 2);
+        assert!(matches!(transcript.entries[1].message, GameMessage::RevealBoard { .. }));
+
+        // The board receipt is garbage, so the very first step is invalid
+        let invalid = transcript.verify(&VerificationPolicy::DevFake).unwrap_err();
+        assert_eq!(invalid.step, 1);
+        assert!(invalid.reason.contains("malformed receipt"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_transcript_order() {
+        let mut transcript = Transcript {
+            header: header(),
+            entries: vec![Entry {
+                player: 1,
+                message: GameMessage::ShotResult {
+                    position: Position::new(0, 0),
+                    hit_type: HitType::Miss,
+                    proof: ProofData {
+                        receipt_bytes: vec![],
+                        commit: RoundCommit {
+                            session: Digest::default(),
+                            turn: 0,
+                            old_state: Digest::default(),
+                            new_state: Digest::default(),
+                            shot: Position::new(0, 0),
+                            hit: HitType::Miss,
+                        },
+                    },
+                },
+            }],
+            outcome: None,
+        };
+
+        let invalid = transcript.verify(&VerificationPolicy::DevFake).unwrap_err();
+        assert_eq!(invalid.step, 1);
+        assert!(invalid.reason.contains("before both boards"));
+
+        transcript.header.image_ids[1].round = Digest::from([7u32; 8]);
+        let invalid = transcript.verify(&VerificationPolicy::Strict).unwrap_err();
+        assert_eq!(invalid.step, 0);
+    }
+}
@@ -0,0 +1,509 @@
+use crate::board_display::BoardDisplay;
+use crate::frontend::{describe, Frontend, View};
+use battleship_core::{
+    Direction, GameMode, GameRules, GameState, HitType, PlacementError, Position, Ship,
+};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::cell::RefCell;
+use std::io::IsTerminal;
+use std::time::Duration;
+
+/// How much of the log is kept for scrolling back
+const LOG_LINES: usize = 500;
+
+/// Full-screen terminal UI: both boards side by side, a log of everything the
+/// game reports and a status bar. Ships are placed and shots aimed with a
+/// cursor. The terminal is restored when it is dropped.
+pub struct Tui {
+    terminal: RefCell<DefaultTerminal>,
+    screen: RefCell<Screen>,
+}
+
+/// Everything on screen, redrawn in full on every update
+#[derive(Default)]
+struct Screen {
+    heading: String,
+    status: String,
+    hints: &'static str,
+    log: Vec<String>,
+    boards: Option<Boards>,
+    cursor: Option<Cursor>,
+    /// Where the last shot was aimed, so the next one starts there
+    last_aim: Option<Position>,
+}
+
+struct Boards {
+    opponent: BoardDisplay,
+    mine: BoardDisplay,
+    fleet: GameState,
+}
+
+enum Cursor {
+    /// Aiming at the opponent's board; `pending` shots are already in the salvo
+    Aim { pos: Position, pending: Vec<Position> },
+    /// Placing `ship` on our board, with why it does not fit if it does not
+    Place { ship: Ship, error: Option<PlacementError> },
+}
+
+impl Tui {
+    /// Take over the terminal; fails when not attached to one, so the caller
+    /// can fall back to the plain text UI
+    pub fn start() -> anyhow::Result<Self> {
+        if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+            anyhow::bail!("not running in a terminal");
+        }
+        let terminal = ratatui::try_init()?;
+        Ok(Self {
+            terminal: RefCell::new(terminal),
+            screen: RefCell::new(Screen::default()),
+        })
+    }
+
+    /// Add to the log; multi-line messages become several entries
+    pub fn log(&self, message: impl AsRef<str>) {
+        {
+            let mut screen = self.screen.borrow_mut();
+            for line in message.as_ref().lines().map(str::trim_end) {
+                if !line.trim().is_empty() {
+                    screen.log.push(line.to_string());
+                }
+            }
+            let excess = screen.log.len().saturating_sub(LOG_LINES);
+            screen.log.drain(..excess);
+        }
+        self.redraw();
+    }
+
+    pub fn status(&self, status: impl Into<String>) {
+        self.screen.borrow_mut().status = status.into();
+        self.redraw();
+    }
+
+    pub fn heading(&self, heading: impl Into<String>) {
+        self.screen.borrow_mut().heading = heading.into();
+        self.redraw();
+    }
+
+    /// Show the shots at both boards, with our fleet on ours
+    pub fn show_boards(&self, opponent: &BoardDisplay, mine: &BoardDisplay, fleet: &GameState) {
+        self.screen.borrow_mut().boards = Some(Boards {
+            opponent: opponent.clone(),
+            mine: mine.clone(),
+            fleet: fleet.clone(),
+        });
+        self.redraw();
+    }
+
+    /// Let the player move a cursor over the opponent's board and pick a cell
+    /// they have not fired at and that is not among the `pending` salvo shots
+    pub fn aim(&self, pending: &[Position]) -> anyhow::Result<Position> {
+        let rules = match &self.screen.borrow().boards {
+            Some(boards) => boards.opponent.rules().clone(),
+            None => anyhow::bail!("the boards are not shown yet"),
+        };
+        let start = self
+            .screen
+            .borrow()
+            .last_aim
+            .unwrap_or(Position::new(rules.width / 2, rules.height / 2));
+        self.set_cursor(Some(Cursor::Aim { pos: start, pending: pending.to_vec() }));
+        self.set_hints("←↑↓→ aim  Enter fire  q quit");
+
+        let mut pos = start;
+        let shot = loop {
+            let key = self.next_key()?;
+            match key.code {
+                KeyCode::Enter | KeyCode::Char(' ') => {
+                    let already = match &self.screen.borrow().boards {
+                        Some(boards) => boards.opponent.already_shot(pos),
+                        None => false,
+                    };
+                    if already {
+                        self.status(format!("You already fired at {}", pos));
+                    } else if pending.contains(&pos) {
+                        self.status(format!("{} is already in this salvo", pos));
+                    } else {
+                        break pos;
+                    }
+                }
+                code => match step(pos, code, &rules) {
+                    Some(next) => {
+                        pos = next;
+                        self.set_cursor(Some(Cursor::Aim { pos, pending: pending.to_vec() }));
+                    }
+                    None if is_quit(&key) => anyhow::bail!("Left the game"),
+                    None => {}
+                },
+            }
+        };
+
+        {
+            let mut screen = self.screen.borrow_mut();
+            screen.last_aim = Some(shot);
+            screen.hints = "";
+        }
+        self.set_cursor(None);
+        Ok(shot)
+    }
+
+    /// Place the fleet with the cursor: move, rotate, place, undo, or shuffle
+    /// the whole fleet at random. The pepper is fresh for every board.
+    pub fn place_fleet(&self, rules: &GameRules) -> anyhow::Result<GameState> {
+        let classes = rules.ships_to_place();
+        let mut state = GameState::new(rules.clone(), rand::random());
+        let mut pos = Position::new(0, 0);
+        let mut dir = Direction::Horizontal;
+        self.heading("SHIP PLACEMENT");
+
+        loop {
+            self.show_boards(&BoardDisplay::new(rules.clone()), &BoardDisplay::new(rules.clone()), &state);
+
+            let Some(class) = classes.get(state.ships.len()) else {
+                // The whole fleet is down; confirm it or keep editing
+                self.set_cursor(None);
+                self.set_hints("Enter start  u undo  a shuffle  q quit");
+                self.status("Fleet ready");
+                let key = self.next_key()?;
+                match key.code {
+                    KeyCode::Enter => break,
+                    KeyCode::Char('u') | KeyCode::Backspace => {
+                        state.ships.pop();
+                    }
+                    KeyCode::Char('a') => state = shuffle(rules)?,
+                    _ if is_quit(&key) => anyhow::bail!("Left the game"),
+                    _ => {}
+                }
+                continue;
+            };
+
+            let ship = Ship::new((*class).clone(), pos, dir);
+            let error = state.clone().add_ship(ship.clone()).err();
+            self.status(match &error {
+                Some(error) => format!("✗ {}", error),
+                None => format!("Placing the {} (length {})", class.name, class.span),
+            });
+            self.set_hints("←↑↓→ move  r rotate  Enter place  u undo  a shuffle  q quit");
+            self.set_cursor(Some(Cursor::Place { ship: ship.clone(), error: error.clone() }));
+
+            let key = self.next_key()?;
+            match key.code {
+                KeyCode::Enter if error.is_none() => state.add_ship(ship)?,
+                KeyCode::Char('r') | KeyCode::Char(' ') => dir = dir.flip(),
+                KeyCode::Char('u') | KeyCode::Backspace => {
+                    state.ships.pop();
+                }
+                KeyCode::Char('a') => state = shuffle(rules)?,
+                code => match step(pos, code, rules) {
+                    Some(next) => pos = next,
+                    None if is_quit(&key) => anyhow::bail!("Left the game"),
+                    None => {}
+                },
+            }
+        }
+
+        self.set_hints("");
+        self.log("✅ Fleet placed");
+        Ok(state)
+    }
+
+    /// Leave the final screen up until the player presses a key
+    pub fn wait_for_key(&self, message: &str) -> anyhow::Result<()> {
+        self.set_hints("any key to leave");
+        self.status(message);
+        self.next_key()?;
+        Ok(())
+    }
+
+    fn set_cursor(&self, cursor: Option<Cursor>) {
+        self.screen.borrow_mut().cursor = cursor;
+        self.redraw();
+    }
+
+    fn set_hints(&self, hints: &'static str) {
+        self.screen.borrow_mut().hints = hints;
+        self.redraw();
+    }
+
+    /// Block until a key is pressed, redrawing when the terminal is resized
+    fn next_key(&self) -> anyhow::Result<KeyEvent> {
+        loop {
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => return Ok(key),
+                Event::Resize(..) => self.redraw(),
+                _ => {}
+            }
+        }
+    }
+
+    fn redraw(&self) {
+        let screen = self.screen.borrow();
+        // A failed redraw is made good by the next update
+        let _ = self.terminal.borrow_mut().draw(|frame| screen.render(frame));
+    }
+}
+
+impl Frontend for Tui {
+    fn say(&self, message: &str) {
+        self.log(message);
+    }
+
+    fn show_turn(&self, view: &View) {
+        self.heading(format!(
+            "{} vs {}  │  {}  │  Your ships: {}  Opponent ships: {}  │  {}",
+            view.player_name,
+            view.opponent_name,
+            view.trust,
+            view.mine.ships_remaining(),
+            view.opponent.ships_remaining(),
+            if view.my_turn { "YOUR TURN" } else { "OPPONENT'S TURN" }
+        ));
+        self.show_boards(view.opponent, view.mine, view.fleet);
+    }
+
+    /// The boards show a single shot; a salvo is listed in the log as well
+    fn show_result(&self, view: &View, ours: bool, shots: &[Position], hits: &[HitType]) {
+        if view.fleet.rules.mode == GameMode::Salvo {
+            let title = if ours { "SALVO RESULT" } else { "OPPONENT'S SALVO RESULT" };
+            let results: Vec<String> = shots
+                .iter()
+                .zip(hits)
+                .map(|(shot, hit)| format!("{} {}", shot, describe(hit)))
+                .collect();
+            self.log(format!("{}: {}", title, results.join(", ")));
+        }
+        self.show_turn(view);
+    }
+
+    fn choose_shot(&self, view: &View, pending: &[Position]) -> anyhow::Result<Position> {
+        self.show_boards(view.opponent, view.mine, view.fleet);
+        self.aim(pending)
+    }
+
+    fn status(&self, status: &str) {
+        Tui::status(self, status);
+    }
+
+    fn proved(&self, what: &str, elapsed: Duration) {
+        let done = format!("{} took {:.1}s", what, elapsed.as_secs_f64());
+        self.log(format!("⏱  {}", done));
+        Tui::status(self, format!("✓ {}", done));
+    }
+
+    fn game_over(&self, won: bool) -> anyhow::Result<()> {
+        self.wait_for_key(if won { "🏆 You won!" } else { "You lost" })
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+impl Screen {
+    fn render(&self, frame: &mut Frame) {
+        let [heading, boards, log, status] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(self.board_height()),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(
+            Paragraph::new(self.heading.as_str()).style(Style::new().add_modifier(Modifier::BOLD)),
+            heading,
+        );
+        if let Some(shown) = &self.boards {
+            self.render_boards(frame, boards, shown);
+        }
+
+        let visible = log.height.saturating_sub(2) as usize;
+        let lines: Vec<Line> = self.log[self.log.len().saturating_sub(visible)..]
+            .iter()
+            .map(|line| Line::raw(line.as_str()))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::new().borders(Borders::ALL).title(" Log ")),
+            log,
+        );
+
+        let bar = Line::from(vec![
+            Span::raw(format!(" {} ", self.status)),
+            Span::styled(format!(" {} ", self.hints), Style::new().add_modifier(Modifier::DIM)),
+        ]);
+        frame.render_widget(
+            Paragraph::new(bar).style(Style::new().add_modifier(Modifier::REVERSED)),
+            status,
+        );
+    }
+
+    fn board_height(&self) -> u16 {
+        match &self.boards {
+            // Rows plus the column labels, borders and the sunk ships line
+            Some(boards) => boards.fleet.rules.height as u16 + 4,
+            None => 0,
+        }
+    }
+
+    fn render_boards(&self, frame: &mut Frame, area: Rect, boards: &Boards) {
+        let rules = &boards.fleet.rules;
+        let width = board_width(rules);
+        let [left, right, _] = Layout::horizontal([
+            Constraint::Length(width),
+            Constraint::Length(width),
+            Constraint::Min(0),
+        ])
+        .areas(area);
+
+        let aim = match &self.cursor {
+            Some(Cursor::Aim { pos, pending }) => Some((*pos, pending.as_slice())),
+            _ => None,
+        };
+        let placing = match &self.cursor {
+            Some(Cursor::Place { ship, error }) => Some((ship, error.is_none())),
+            _ => None,
+        };
+
+        let opponent = grid(rules, &boards.opponent, |pos, symbol| match aim {
+            Some((cursor, _)) if cursor == pos => {
+                Span::styled(format!(" {} ", symbol), cell_style(symbol).add_modifier(Modifier::REVERSED))
+            }
+            Some((_, pending)) if pending.contains(&pos) => {
+                Span::styled(" + ", Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD))
+            }
+            _ => Span::styled(format!(" {} ", symbol), cell_style(symbol)),
+        });
+        let mine = grid(rules, &boards.mine, |pos, _| {
+            let symbol = boards.mine.cell(pos, Some(&boards.fleet));
+            match placing {
+                Some((ship, valid)) if ship.points().any(|p| p == pos) => Span::styled(
+                    format!(" {} ", ship.class.symbol),
+                    Style::new()
+                        .fg(Color::Black)
+                        .bg(if valid { Color::Green } else { Color::Red }),
+                ),
+                _ => Span::styled(format!(" {} ", symbol), cell_style(symbol)),
+            }
+        });
+
+        frame.render_widget(
+            board_widget(opponent, " Opponent (ships hidden) ", &boards.opponent),
+            left,
+        );
+        frame.render_widget(board_widget(mine, " Your fleet ", &boards.mine), right);
+    }
+}
+
+/// Columns a board takes: row labels, three per cell and the border
+fn board_width(rules: &GameRules) -> u16 {
+    (rules.height - 1).to_string().len() as u16 + 3 * rules.width as u16 + 3
+}
+
+/// The labelled rows of a board, each cell drawn by `cell`
+fn grid<'a>(
+    rules: &GameRules,
+    display: &BoardDisplay,
+    cell: impl Fn(Position, char) -> Span<'a>,
+) -> Vec<Line<'a>> {
+    let label = (rules.height - 1).to_string().len();
+    let mut header = vec![Span::raw(" ".repeat(label + 1))];
+    header.extend((0..rules.width).map(|x| Span::raw(format!("{:^3}", x))));
+
+    let mut lines = vec![Line::from(header)];
+    for y in 0..rules.height {
+        let mut row = vec![Span::raw(format!("{:>label$} ", y))];
+        row.extend((0..rules.width).map(|x| {
+            let pos = Position::new(x, y);
+            cell(pos, display.cell(pos, None))
+        }));
+        lines.push(Line::from(row));
+    }
+    lines
+}
+
+fn board_widget<'a>(lines: Vec<Line<'a>>, title: &'a str, display: &BoardDisplay) -> Paragraph<'a> {
+    let sunk: Vec<String> = display.sunk_ships().iter().map(|ship| ship.to_string()).collect();
+    let mut lines = lines;
+    if !sunk.is_empty() {
+        lines.push(Line::styled(format!("Sunk: {}", sunk.join(", ")), Style::new().fg(Color::Red)));
+    }
+    Paragraph::new(lines).block(Block::new().borders(Borders::ALL).title(title))
+}
+
+fn cell_style(symbol: char) -> Style {
+    match symbol {
+        'X' => Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+        'O' => Style::new().fg(Color::Cyan),
+        '~' => Style::new().fg(Color::Blue),
+        _ => Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+    }
+}
+
+/// Move the cursor one cell with the arrow or vi keys, staying on the board
+fn step(pos: Position, code: KeyCode, rules: &GameRules) -> Option<Position> {
+    let (x, y) = match code {
+        KeyCode::Left | KeyCode::Char('h') => (pos.x.checked_sub(1)?, pos.y),
+        KeyCode::Right | KeyCode::Char('l') => (pos.x + 1, pos.y),
+        KeyCode::Up | KeyCode::Char('k') => (pos.x, pos.y.checked_sub(1)?),
+        KeyCode::Down | KeyCode::Char('j') => (pos.x, pos.y + 1),
+        _ => return None,
+    };
+    Some(Position::new(x.min(rules.width - 1), y.min(rules.height - 1)))
+}
+
+/// `q`, Esc or Ctrl-C, which raw mode delivers as a key instead of a signal
+fn is_quit(key: &KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+        || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
+fn shuffle(rules: &GameRules) -> anyhow::Result<GameState> {
+    GameState::random(rules, &mut rand::thread_rng())
+        .ok_or_else(|| anyhow::anyhow!("Could not fit the fleet on the board"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use battleship_core::HitType;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn test_tui_render() {
+        let rules = GameRules::classic();
+        let fleet = GameState::random(&rules, &mut rand::thread_rng()).unwrap();
+        let mut opponent = BoardDisplay::new(rules.clone());
+        opponent.record_shot(Position::new(0, 0), HitType::Hit);
+
+        let screen = Screen {
+            heading: "Alice vs Bob".to_string(),
+            status: "⏳ Waiting for Bob".to_string(),
+            log: vec!["✅ Proof verified!".to_string()],
+            boards: Some(Boards {
+                opponent,
+                mine: BoardDisplay::new(rules.clone()),
+                fleet,
+            }),
+            cursor: Some(Cursor::Aim { pos: Position::new(1, 0), pending: vec![] }),
+            ..Screen::default()
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| screen.render(frame)).unwrap();
+        let text: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+
+        for expected in ["Alice vs Bob", "Opponent (ships hidden)", "Your fleet", "Proof verified", "Waiting for Bob"] {
+            assert!(text.contains(expected), "{} is not on screen", expected);
+        }
+        // The hit on the opponent's board and our carrier are both drawn
+        assert!(text.contains(" X "));
+        assert!(text.contains(" A "));
+    }
+}
@@ -0,0 +1,275 @@
+use battleship_guests::{FLEET_SUNK_ID, INIT_ID, ROUND_ID, SALVO_ID};
+use risc0_zkvm::{sha::Digest, InnerReceipt, Receipt, VerifierContext};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Image IDs of the guest programs a player was built with
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageIds {
+    pub init: Digest,
+    pub round: Digest,
+    pub salvo: Digest,
+    pub fleet_sunk: Digest,
+}
+
+impl ImageIds {
+    /// Image IDs of the guests compiled into this binary
+    pub fn local() -> Self {
+        Self {
+            init: INIT_ID.into(),
+            round: ROUND_ID.into(),
+            salvo: SALVO_ID.into(),
+            fleet_sunk: FLEET_SUNK_ID.into(),
+        }
+    }
+
+    /// Parse one `init round salvo fleet_sunk` line of hex digests
+    fn parse(line: &str) -> anyhow::Result<Self> {
+        let ids = line
+            .split_whitespace()
+            .map(|hex| {
+                <Digest as hex::FromHex>::from_hex(hex)
+                    .map_err(|e| anyhow::anyhow!("invalid image ID {}: {}", hex, e))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        match ids[..] {
+            [init, round, salvo, fleet_sunk] => Ok(Self {
+                init,
+                round,
+                salvo,
+                fleet_sunk,
+            }),
+            _ => anyhow::bail!(
+                "expected 4 image IDs (init round salvo fleet_sunk), got {}",
+                ids.len()
+            ),
+        }
+    }
+}
+
+impl Display for ImageIds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {} {}", self.init, self.round, self.salvo, self.fleet_sunk)
+    }
+}
+
+/// How a receipt is packaged. Composite receipts are quickest to prove and the
+/// largest; succinct ones are compressed by recursion to a couple of hundred
+/// KiB, and Groth16 ones wrap that in a SNARK of a few hundred bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReceiptKind {
+    Composite,
+    Succinct,
+    Groth16,
+}
+
+impl ReceiptKind {
+    /// The kind of a real receipt; fake receipts have none and are left to the policy
+    pub fn of(receipt: &Receipt) -> Option<Self> {
+        match receipt.inner {
+            InnerReceipt::Composite(_) => Some(Self::Composite),
+            InnerReceipt::Succinct(_) => Some(Self::Succinct),
+            InnerReceipt::Groth16(_) => Some(Self::Groth16),
+            _ => None,
+        }
+    }
+
+    /// The kind both players prove with: the first player's favourite that the
+    /// other also accepts
+    pub fn negotiate(first: &[Self], second: &[Self]) -> Option<Self> {
+        first.iter().copied().find(|kind| second.contains(kind))
+    }
+
+    /// Refuse a real receipt of a kind we did not agree to accept
+    pub fn check(receipt: &Receipt, accepted: &[Self]) -> anyhow::Result<()> {
+        match Self::of(receipt) {
+            Some(kind) if !accepted.contains(&kind) => {
+                anyhow::bail!("{} receipts are not accepted", kind)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// A comma separated list such as `succinct,composite`, as `--receipts` takes it
+    pub fn format_list(kinds: &[Self]) -> String {
+        kinds.iter().map(Self::to_string).collect::<Vec<_>>().join(",")
+    }
+}
+
+impl Display for ReceiptKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Composite => write!(f, "composite"),
+            Self::Succinct => write!(f, "succinct"),
+            Self::Groth16 => write!(f, "groth16"),
+        }
+    }
+}
+
+impl FromStr for ReceiptKind {
+    type Err = anyhow::Error;
+
+    fn from_str(spec: &str) -> anyhow::Result<Self> {
+        match spec {
+            "composite" => Ok(Self::Composite),
+            "succinct" => Ok(Self::Succinct),
+            "groth16" => Ok(Self::Groth16),
+            _ => anyhow::bail!(
+                "unknown receipt kind '{}'; use composite, succinct or groth16",
+                spec
+            ),
+        }
+    }
+}
+
+/// Which receipts a player accepts; both players must agree on it in the handshake
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VerificationPolicy {
+    /// Real proofs only, from guests identical to ours
+    Strict,
+    /// Dev mode fake receipts are accepted; nothing is cryptographically proven
+    DevFake,
+    /// Real proofs from any of the listed guest builds
+    Pinned(Vec<ImageIds>),
+}
+
+impl VerificationPolicy {
+    /// Pick the policy from the environment.
+    ///
+    /// `BATTLESHIP_PINNED_IMAGES` names a file with one `init round salvo fleet_sunk`
+    /// line of hex image IDs per accepted build. Otherwise `RISC0_DEV_MODE`
+    /// selects dev-fake and anything else is strict.
+    pub fn from_env() -> anyhow::Result<Self> {
+        if let Ok(path) = std::env::var("BATTLESHIP_PINNED_IMAGES") {
+            return Self::load_pinned(&path);
+        }
+        if dev_mode_enabled() {
+            return Ok(Self::DevFake);
+        }
+        Ok(Self::Strict)
+    }
+
+    pub fn load_pinned(path: &str) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read pinned image IDs from {}: {}", path, e))?;
+
+        let pinned = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(ImageIds::parse)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        if pinned.is_empty() {
+            anyhow::bail!("{} does not pin any image IDs", path);
+        }
+        Ok(Self::Pinned(pinned))
+    }
+
+    /// Make sure this client can produce receipts the policy accepts
+    pub fn check_local(&self) -> anyhow::Result<()> {
+        if !matches!(self, Self::DevFake) && dev_mode_enabled() {
+            anyhow::bail!(
+                "RISC0_DEV_MODE is set, so only fake receipts can be produced; \
+                 unset it or use the dev-fake verification policy"
+            );
+        }
+        Ok(())
+    }
+
+    /// Decide whether we can play against a peer, returning the image IDs their
+    /// receipts will be verified against
+    pub fn accept_peer(
+        &self,
+        peer_policy: &VerificationPolicy,
+        peer_ids: &ImageIds,
+    ) -> anyhow::Result<ImageIds> {
+        if peer_policy != self {
+            anyhow::bail!(
+                "verification policies differ: ours is {}, the opponent's is {}",
+                self,
+                peer_policy
+            );
+        }
+
+        if self.accepts_ids(peer_ids) {
+            return Ok(*peer_ids);
+        }
+        match self {
+            Self::Pinned(_) => anyhow::bail!(
+                "opponent's guest image IDs [{}] are not pinned",
+                peer_ids
+            ),
+            _ => anyhow::bail!(
+                "guest image IDs differ: ours are [{}], the opponent's are [{}]",
+                ImageIds::local(),
+                peer_ids
+            ),
+        }
+    }
+
+    /// Whether receipts from guests with these image IDs are acceptable at all
+    pub fn accepts_ids(&self, ids: &ImageIds) -> bool {
+        match self {
+            Self::Strict | Self::DevFake => *ids == ImageIds::local(),
+            Self::Pinned(pinned) => pinned.contains(ids),
+        }
+    }
+
+    /// Verify a receipt against one of the peer's image IDs under this policy
+    pub fn verify(&self, receipt: &Receipt, image_id: Digest) -> anyhow::Result<()> {
+        let allow_fake = matches!(self, Self::DevFake);
+        if !allow_fake && matches!(receipt.inner, InnerReceipt::Fake(_)) {
+            anyhow::bail!("fake dev mode receipt rejected under the {} policy", self);
+        }
+
+        let ctx = VerifierContext::default().with_dev_mode(allow_fake);
+        receipt.verify_with_context(&ctx, image_id)?;
+        Ok(())
+    }
+
+    /// One-line trust level for the board header
+    pub fn trust_level(&self) -> String {
+        match self {
+            Self::Strict => "🔒 STRICT - real proofs, identical guest images".to_string(),
+            Self::DevFake => "⚠️  DEV-FAKE - proofs are NOT checked, no security".to_string(),
+            Self::Pinned(pinned) => {
+                format!("📌 PINNED - real proofs, {} accepted guest builds", pinned.len())
+            }
+        }
+    }
+}
+
+impl Display for VerificationPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Strict => write!(f, "strict"),
+            Self::DevFake => write!(f, "dev-fake"),
+            Self::Pinned(pinned) => write!(f, "pinned ({} builds)", pinned.len()),
+        }
+    }
+}
+
+/// `strict`, `dev-fake` or `pinned:<file>`, as accepted on the command line
+impl FromStr for VerificationPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(spec: &str) -> anyhow::Result<Self> {
+        match spec {
+            "strict" => Ok(Self::Strict),
+            "dev-fake" => Ok(Self::DevFake),
+            _ => match spec.strip_prefix("pinned:") {
+                Some(path) => Self::load_pinned(path),
+                None => anyhow::bail!("unknown policy '{}'; use strict, dev-fake or pinned:<file>", spec),
+            },
+        }
+    }
+}
+
+fn dev_mode_enabled() -> bool {
+    std::env::var("RISC0_DEV_MODE")
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}